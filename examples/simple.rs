@@ -1,11 +1,15 @@
-use xd2d::{App, Xd, XdResult};
+use xd2d::{rect, App, Color, Xd, XdResult};
 
 struct ExampleApp;
 
 impl App for ExampleApp {
     fn update(&mut self, _xd: &mut Xd) {}
 
-    fn draw(&mut self, _xd: &mut Xd) {}
+    fn draw(&mut self, xd: &mut Xd) {
+        xd.painter.clear(Color::CORNFLOWER_BLUE);
+        xd.painter
+            .filled_rect(rect(100.0, 100.0, 200.0, 150.0), Color::ORANGE);
+    }
 }
 
 fn main() -> XdResult<()> {
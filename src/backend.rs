@@ -47,6 +47,7 @@ impl Backend {
             event_loop::{ControlFlow, EventLoop},
             ContextBuilder, GlRequest,
         };
+        use std::rc::Rc;
 
         let event_loop = EventLoop::new();
         let window = Self::create_window_builder(xd.window_settings.clone());
@@ -59,6 +60,8 @@ impl Backend {
         // make it current and load function pointers
         let gl_context = unsafe { gl_context.make_current() }.map_err(|e| e.1)?;
         let gl = unsafe { glow::Context::from_loader_function(|s| gl_context.get_proc_address(s)) };
+        let gl = Rc::new(gl);
+        xd.gl = Some(gl.clone());
 
         // set the initial window size
         {
@@ -66,6 +69,9 @@ impl Backend {
             (xd.width, xd.height) = (inner_size.width, inner_size.height);
         }
 
+        // create the painter's GL resources
+        unsafe { xd.painter.gl_init(&gl) };
+
         // call init()
         app.init(&mut xd);
 
@@ -73,13 +79,21 @@ impl Backend {
         event_loop.run(move |event, _, control_flow| {
             *control_flow = ControlFlow::Poll;
             match event {
-                Event::LoopDestroyed => (),
+                Event::LoopDestroyed => unsafe { xd.painter.gl_destroy(&gl) },
                 Event::MainEventsCleared => gl_context.window().request_redraw(),
                 Event::RedrawRequested(_) => {
                     unsafe {
                         gl.clear(glow::COLOR_BUFFER_BIT);
                     }
+                    xd.painter.begin(xd.width, xd.height);
                     app.draw(&mut xd);
+                    unsafe {
+                        xd.painter.gl_render(&gl);
+                    }
+                    if std::mem::take(&mut xd.screenshot_requested) {
+                        let rect = crate::Rect::new(0.0, 0.0, xd.width as f32, xd.height as f32);
+                        xd.screenshot = Some(unsafe { xd.painter.read_pixels(&gl, None, rect) });
+                    }
                     if !xd.ignore_swapbuffers {
                         gl_context.swap_buffers().unwrap();
                     }
@@ -94,7 +108,6 @@ impl Backend {
                         let inner_size = gl_context.window().inner_size();
                         (xd.width, xd.height) = (inner_size.width, inner_size.height);
                     }
-                    // TODO: free gl resources
                     WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
                     _ => (),
                 },
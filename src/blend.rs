@@ -0,0 +1,126 @@
+use glow::{
+    DST_COLOR, FUNC_ADD, FUNC_REVERSE_SUBTRACT, ONE, ONE_MINUS_SRC_ALPHA, ONE_MINUS_SRC_COLOR, ZERO,
+};
+
+/// How a draw combines its colors with the framebuffer.
+///
+/// Blending works on premultiplied colors: the default shader multiplies the
+/// color by its alpha first, unless the mode says it already is. The alpha
+/// channel of the framebuffer accumulates coverage the same way for every mode
+/// except [`BlendMode::Replace`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum BlendMode {
+    /// Draw over the framebuffer, letting it show through by `1 - alpha`.
+    #[default]
+    Alpha,
+    /// Like [`BlendMode::Alpha`], for colors and textures already multiplied
+    /// by their alpha.
+    PremultipliedAlpha,
+    /// Add the color to the framebuffer. Good for particles and lights.
+    Additive,
+    /// Multiply the framebuffer with the color, darkening it.
+    Multiply,
+    /// Multiply the inverse of the framebuffer with the inverse of the color,
+    /// brightening it.
+    Screen,
+    /// Subtract the color from the framebuffer.
+    Subtract,
+    /// Overwrite the framebuffer with the premultiplied color, alpha included.
+    Replace,
+}
+
+impl BlendMode {
+    /// Whether colors drawn with this mode are already multiplied by their alpha.
+    #[inline]
+    pub const fn is_premultiplied(self) -> bool {
+        matches!(self, Self::PremultipliedAlpha)
+    }
+
+    /// The matching GL color blend equation and `[src_rgb, dst_rgb, src_alpha, dst_alpha]`
+    /// factors, for premultiplied fragment colors. Alpha always uses `FUNC_ADD`.
+    #[inline]
+    pub const fn to_gl(self) -> (u32, [u32; 4]) {
+        match self {
+            Self::Alpha | Self::PremultipliedAlpha => (
+                FUNC_ADD,
+                [ONE, ONE_MINUS_SRC_ALPHA, ONE, ONE_MINUS_SRC_ALPHA],
+            ),
+            Self::Additive => (FUNC_ADD, [ONE, ONE, ONE, ONE_MINUS_SRC_ALPHA]),
+            Self::Multiply => (
+                FUNC_ADD,
+                [DST_COLOR, ONE_MINUS_SRC_ALPHA, ONE, ONE_MINUS_SRC_ALPHA],
+            ),
+            Self::Screen => (
+                FUNC_ADD,
+                [ONE, ONE_MINUS_SRC_COLOR, ONE, ONE_MINUS_SRC_ALPHA],
+            ),
+            Self::Subtract => (FUNC_REVERSE_SUBTRACT, [ONE, ONE, ONE, ONE_MINUS_SRC_ALPHA]),
+            Self::Replace => (FUNC_ADD, [ONE, ZERO, ONE, ZERO]),
+        }
+    }
+
+    /// Blend the premultiplied color `src` into `dst` like the GL blend state
+    /// from [`BlendMode::to_gl`] does, without clamping.
+    pub fn apply(self, src: [f32; 4], dst: [f32; 4]) -> [f32; 4] {
+        if self == Self::Replace {
+            return src;
+        }
+        let sa = src[3];
+        let [r, g, b] = std::array::from_fn(|i| {
+            let (s, d) = (src[i], dst[i]);
+            match self {
+                Self::Alpha | Self::PremultipliedAlpha => s + d * (1.0 - sa),
+                Self::Additive => s + d,
+                Self::Multiply => s * d + d * (1.0 - sa),
+                Self::Screen => s + d * (1.0 - s),
+                Self::Subtract => d - s,
+                Self::Replace => s,
+            }
+        });
+        [r, g, b, sa + dst[3] * (1.0 - sa)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BlendMode;
+    use crate::{rect, rgb, rgba, Color, Painter, SoftwareRenderer};
+
+    #[test]
+    fn blend_modes() {
+        let modes = [
+            (BlendMode::Alpha, rgb(192, 64, 64)),
+            // the color is taken as premultiplied, so red is over-bright
+            (BlendMode::PremultipliedAlpha, rgb(255, 64, 64)),
+            (BlendMode::Additive, rgb(255, 128, 128)),
+            (BlendMode::Multiply, rgb(128, 64, 64)),
+            (BlendMode::Screen, rgb(192, 128, 128)),
+            (BlendMode::Subtract, rgb(0, 128, 128)),
+            (BlendMode::Replace, rgba(128, 0, 0, 128)),
+        ];
+        let mut painter = Painter::default();
+        painter.begin(modes.len() as u32, 1);
+        // half transparent red over gray, once per mode
+        painter.clear(rgb(128, 128, 128));
+        for (x, (mode, _)) in modes.iter().enumerate() {
+            painter.set_blend_mode(*mode);
+            painter.filled_rect(rect(x as f32, 0.0, 1.0, 1.0), rgba(255, 0, 0, 128));
+        }
+        // switching modes splits draws that would merge otherwise
+        assert_eq!(painter.stats().draw_calls, modes.len());
+
+        let mut fb = SoftwareRenderer::new(painter.width, painter.height);
+        fb.render(&painter);
+        for (x, (mode, expected)) in modes.iter().enumerate() {
+            assert_eq!(fb.pixel(x as u32, 0), *expected, "{mode:?}");
+        }
+
+        // additive blending onto an empty framebuffer still accumulates coverage
+        painter.begin(1, 1);
+        painter.set_blend_mode(BlendMode::Additive);
+        painter.filled_rect(rect(0.0, 0.0, 1.0, 1.0), Color::RED);
+        fb.render(&painter);
+        assert_eq!(fb.pixel(0, 0), Color::RED);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{ShaderType, TextureId};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -6,6 +7,38 @@ pub enum XdError {
     CreationError(#[from] glutin::CreationError),
     #[error("GL context error: {0}")]
     ContextError(#[from] glutin::ContextError),
+    #[error("GL error: {0}")]
+    Gl(String),
+    #[error("expected {expected} bytes of RGBA8 pixel data, got {got}")]
+    InvalidPixelData { expected: usize, got: usize },
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("PNG encoding error: {0}")]
+    PngEncoding(#[from] png::EncodingError),
+    #[error("PNG decoding error: {0}")]
+    PngDecoding(#[from] png::DecodingError),
+    #[error("invalid font data")]
+    InvalidFont,
+    #[error("framebuffer is incomplete, status 0x{0:x}")]
+    IncompleteFramebuffer(u32),
+    #[error("{stage} shader compile error{}: {log}", line.map(|l| format!(" at line {l}")).unwrap_or_default())]
+    ShaderCompile {
+        stage: ShaderType,
+        /// Line of the first error in the source, if the log names one.
+        line: Option<u32>,
+        log: String,
+    },
+    #[error("shader link error: {0}")]
+    ShaderLink(String),
+    #[error("region {width}x{height} at ({x}, {y}) is out of bounds")]
+    RegionOutOfBounds {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    #[error("unknown texture {0:?}")]
+    UnknownTexture(TextureId),
 }
 
 pub type XdResult<T> = Result<T, XdError>;
@@ -0,0 +1,309 @@
+use crate::{Color, Painter, Path, Pos2, Rect, Vec2};
+
+/// Decides which areas of overlapping or self-intersecting polygons are inside.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum FillRule {
+    /// Inside where the contours wind around a point a nonzero number of times,
+    /// so holes need to wind the opposite way of their outline.
+    #[default]
+    NonZero,
+    /// Inside where a ray from a point crosses an odd number of edges, so every
+    /// nested contour toggles between filled and empty.
+    EvenOdd,
+}
+
+impl FillRule {
+    #[inline]
+    fn is_inside(self, winding: i32) -> bool {
+        match self {
+            Self::NonZero => winding != 0,
+            Self::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
+/// A non-horizontal polygon edge, with `top.y < bottom.y` on a y-down screen.
+#[derive(Clone, Copy)]
+struct Edge {
+    top: Vec2,
+    bottom: Vec2,
+    /// +1 if the contour goes down along this edge, -1 if it goes up.
+    winding: i32,
+}
+
+impl Edge {
+    fn new(a: Vec2, b: Vec2) -> Option<Self> {
+        match a.y.partial_cmp(&b.y)? {
+            std::cmp::Ordering::Less => Some(Self {
+                top: a,
+                bottom: b,
+                winding: 1,
+            }),
+            std::cmp::Ordering::Greater => Some(Self {
+                top: b,
+                bottom: a,
+                winding: -1,
+            }),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// X where the edge crosses the horizontal line at `y`, exact at the end points
+    /// so trapezoids above and below a vertex share their corners.
+    #[inline]
+    fn x_at(&self, y: f32) -> f32 {
+        if y <= self.top.y {
+            self.top.x
+        } else if y >= self.bottom.y {
+            self.bottom.x
+        } else {
+            let t = (y - self.top.y) / (self.bottom.y - self.top.y);
+            self.top.x + (self.bottom.x - self.top.x) * t
+        }
+    }
+
+    /// Y where the two edges cross, if they cross strictly inside both of them.
+    fn intersection_y(&self, other: &Self) -> Option<f32> {
+        let (p, r) = (self.top, self.bottom - self.top);
+        let (q, s) = (other.top, other.bottom - other.top);
+        let cross = |a: Vec2, b: Vec2| a.x * b.y - a.y * b.x;
+        let denom = cross(r, s);
+        if denom == 0.0 {
+            return None; // parallel
+        }
+        let t = cross(q - p, s) / denom;
+        let u = cross(q - p, r) / denom;
+        (t > 0.0 && t < 1.0 && u > 0.0 && u < 1.0).then_some(p.y + r.y * t)
+    }
+}
+
+/// Split the area inside `contours` into trapezoids, given as their top left,
+/// top right, bottom right and bottom left corners.
+///
+/// A sweep from top to bottom cuts the plane into horizontal slabs at every
+/// vertex and edge crossing, so the edges inside a slab never cross and can be
+/// sorted left to right. Walking them while counting the winding number gives
+/// the spans that are inside. Only the edges spanning the current slab are
+/// looked at, and crossings only need checking between neighbors.
+fn tessellate(contours: &[&[Pos2]], rule: FillRule) -> Vec<[Vec2; 4]> {
+    let mut edges = Vec::new();
+    for contour in contours {
+        let n = contour.len();
+        if n < 3 {
+            continue;
+        }
+        // every contour is implicitly closed
+        for i in 0..n {
+            let (a, b) = (contour[i].to_vec2(), contour[(i + 1) % n].to_vec2());
+            if a.is_finite() && b.is_finite() {
+                edges.extend(Edge::new(a, b));
+            }
+        }
+    }
+    edges.sort_by(|a, b| a.top.y.total_cmp(&b.top.y));
+
+    let mut trapezoids = Vec::new();
+    // edges crossing the current slab, and the first edge below it
+    let mut active: Vec<&Edge> = Vec::new();
+    let mut next = 0;
+    let Some(mut y0) = edges.first().map(|e| e.top.y) else {
+        return trapezoids;
+    };
+    loop {
+        active.retain(|e| e.bottom.y > y0);
+        while let Some(edge) = edges.get(next).filter(|e| e.top.y <= y0) {
+            active.push(edge);
+            next += 1;
+        }
+        if active.is_empty() {
+            match edges.get(next) {
+                Some(edge) => y0 = edge.top.y,
+                None => break,
+            }
+            continue;
+        }
+
+        // the slab ends at the next vertex, or at the first crossing before it.
+        // Edges crossing inside the slab are ordered differently at its middle
+        // than at one of its ends, so some of them are neighbors there
+        let mut y1 = active
+            .iter()
+            .map(|e| e.bottom.y)
+            .fold(f32::INFINITY, f32::min);
+        if let Some(edge) = edges.get(next) {
+            y1 = y1.min(edge.top.y);
+        }
+        loop {
+            let mid = (y0 + y1) * 0.5;
+            active.sort_by(|a, b| a.x_at(mid).total_cmp(&b.x_at(mid)));
+            let crossing = active
+                .windows(2)
+                .filter_map(|pair| pair[0].intersection_y(pair[1]))
+                .filter(|&y| y > y0 && y < y1)
+                .min_by(f32::total_cmp);
+            match crossing {
+                Some(y) => y1 = y,
+                None => break,
+            }
+        }
+
+        let mut winding = 0;
+        let mut left = None;
+        for &edge in &active {
+            let was_inside = rule.is_inside(winding);
+            winding += edge.winding;
+            match (was_inside, rule.is_inside(winding)) {
+                (false, true) => left = Some(edge),
+                (true, false) => {
+                    let left = left.take().unwrap();
+                    let tl = Vec2::new(left.x_at(y0), y0);
+                    let bl = Vec2::new(left.x_at(y1), y1);
+                    let tr = Vec2::new(edge.x_at(y0), y0);
+                    let br = Vec2::new(edge.x_at(y1), y1);
+                    trapezoids.push([tl, tr, br, bl]);
+                }
+                _ => (),
+            }
+        }
+        y0 = y1;
+    }
+    trapezoids
+}
+
+impl Painter {
+    /// Fill a polygon, which may be concave or intersect itself.
+    ///
+    /// The last point connects back to the first one.
+    #[inline]
+    pub fn filled_polygon(&mut self, points: &[Pos2], rule: FillRule, color: impl Into<Color>) {
+        self.filled_polygons(&[points], rule, color);
+    }
+
+    /// Fill the area enclosed by several contours at once, for example an outline
+    /// and its holes. `rule` decides which overlapping areas are inside.
+    ///
+    /// Texture coordinates map the bounding box of all points to `[0, 1]`.
+    pub fn filled_polygons(
+        &mut self,
+        contours: &[&[Pos2]],
+        rule: FillRule,
+        color: impl Into<Color>,
+    ) {
+        let color = color.into();
+        let Some(first) = contours.iter().find_map(|c| c.first()) else {
+            return;
+        };
+        let bounds = contours
+            .iter()
+            .flat_map(|c| c.iter())
+            .fold(Rect::from_min_max(*first, *first), |r, p| {
+                Rect::from_min_max(r.min().min(*p), r.max().max(*p))
+            });
+        let (min, size) = (bounds.min().to_vec2(), bounds.size());
+        let uv = |p: Vec2| {
+            let uv = (p - min) / size;
+            Vec2::new(
+                if size.x > 0.0 { uv.x } else { 0.0 },
+                if size.y > 0.0 { uv.y } else { 0.0 },
+            )
+        };
+
+        for trapezoid in tessellate(contours, rule) {
+            let base = self.reserve_indexed(4);
+            for p in trapezoid {
+                self.push_vertex(p, uv(p), color);
+            }
+            self.push_indices(base, &[0, 1, 2, 0, 2, 3]);
+        }
+        self.queue_indexed_draw();
+    }
+
+    /// Flatten `path` to the current zoom level and fill it, closing open subpaths.
+    pub fn fill_path(&mut self, path: &Path, rule: FillRule, color: impl Into<Color>) {
+        let contours = path.flatten(self.curve_tolerance());
+        let contours: Vec<&[Pos2]> = contours.iter().map(|c| c.points.as_slice()).collect();
+        self.filled_polygons(&contours, rule, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FillRule;
+    use crate::{pos2, rgba, Color, Painter, Pos2, SoftwareRenderer, Vec2};
+
+    fn render(painter: &Painter) -> SoftwareRenderer {
+        let mut fb = SoftwareRenderer::new(painter.width, painter.height);
+        fb.render(painter);
+        fb
+    }
+
+    #[test]
+    fn concave_and_holes() {
+        let mut painter = Painter::default();
+        painter.begin(32, 32);
+        painter.clear(Color::BLACK);
+        // an L shape, translucent so any overlap would show up brighter
+        let l = [
+            pos2(4.0, 4.0),
+            pos2(12.0, 4.0),
+            pos2(12.0, 20.0),
+            pos2(28.0, 20.0),
+            pos2(28.0, 28.0),
+            pos2(4.0, 28.0),
+        ];
+        painter.filled_polygon(&l, FillRule::NonZero, rgba(255, 255, 255, 128));
+        let fb = render(&painter);
+        let covered = fb.pixels().chunks(4).filter(|p| p[0] == 128).count();
+        assert_eq!(covered, 8 * 24 + 16 * 8);
+        assert!(fb.pixels().chunks(4).all(|p| p[0] <= 128));
+        assert_eq!(fb.pixel(20, 10), Color::BLACK);
+
+        // a square with a square hole, the hole winding the other way
+        let outer = [
+            pos2(4.0, 4.0),
+            pos2(28.0, 4.0),
+            pos2(28.0, 28.0),
+            pos2(4.0, 28.0),
+        ];
+        let hole = [
+            pos2(10.0, 10.0),
+            pos2(10.0, 22.0),
+            pos2(22.0, 22.0),
+            pos2(22.0, 10.0),
+        ];
+        for rule in [FillRule::NonZero, FillRule::EvenOdd] {
+            painter.begin(32, 32);
+            painter.filled_polygons(&[&outer, &hole], rule, Color::WHITE);
+            let fb = render(&painter);
+            assert_eq!(fb.pixel(16, 16), Color::INVISIBLE, "{rule:?}");
+            assert_eq!(fb.pixel(6, 16), Color::WHITE, "{rule:?}");
+            let covered = fb.pixels().chunks(4).filter(|p| p[0] == 255).count();
+            assert_eq!(covered, 24 * 24 - 12 * 12, "{rule:?}");
+        }
+    }
+
+    #[test]
+    fn self_intersecting_star() {
+        // a pentagram, its center is wound twice
+        let star: Vec<Pos2> = (0..5)
+            .map(|i| {
+                let angle = (i as f32 * 0.4 - 0.25) * std::f32::consts::TAU;
+                pos2(32.0, 32.0) + Vec2::angled(angle) * 28.0
+            })
+            .collect();
+        let mut painter = Painter::default();
+        for (rule, center) in [
+            (FillRule::NonZero, Color::WHITE),
+            (FillRule::EvenOdd, Color::INVISIBLE),
+        ] {
+            painter.begin(64, 64);
+            painter.filled_polygon(&star, rule, Color::WHITE);
+            let fb = render(&painter);
+            assert_eq!(fb.pixel(32, 32), center, "{rule:?}");
+            // the top spike is outside the inner pentagon either way
+            assert_eq!(fb.pixel(32, 8), Color::WHITE, "{rule:?}");
+            assert_eq!(fb.pixel(4, 4), Color::INVISIBLE, "{rule:?}");
+        }
+    }
+}
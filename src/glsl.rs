@@ -0,0 +1,209 @@
+use crate::ShaderType;
+use glow::{Context, HasContext};
+
+/// Name of the fragment output declared for sources writing `gl_FragColor`.
+const FRAG_COLOR: &str = "xd_FragColor";
+
+/// GLSL version and syntax accepted by a GL context.
+///
+/// Shaders are written once, and [`GlslDialect::translate`] adapts them: it
+/// puts the right `#version` line and default precisions first, turns
+/// `attribute`/`varying` into `in`/`out`, `texture2D` into `texture` and
+/// `gl_FragColor` into a declared output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GlslDialect {
+    /// `#version 330 core`, for desktop OpenGL 3.3 and later.
+    Glsl330,
+    /// `#version 300 es`, for OpenGL ES 3 and WebGL 2.
+    Glsl300Es,
+}
+
+impl GlslDialect {
+    /// The dialect of the current context `gl`.
+    pub fn detect(gl: &Context) -> Self {
+        if gl.version().is_embedded {
+            Self::Glsl300Es
+        } else {
+            Self::Glsl330
+        }
+    }
+
+    #[inline]
+    pub const fn version_line(self) -> &'static str {
+        match self {
+            Self::Glsl330 => "#version 330 core",
+            Self::Glsl300Es => "#version 300 es",
+        }
+    }
+
+    /// Rewrite the `stage` shader `source` for this dialect.
+    #[inline]
+    pub fn translate(self, source: &str, stage: ShaderType) -> String {
+        self.translate_with_offset(source, stage).0
+    }
+
+    /// Like [`GlslDialect::translate`], also returning how many lines were
+    /// added in front of the source, to map compile errors back to it.
+    pub(crate) fn translate_with_offset(self, source: &str, stage: ShaderType) -> (String, u32) {
+        let mut header = vec![self.version_line().to_owned()];
+        // the version and extension directives have to come before any code,
+        // blank them where they are so the line numbers stay the same
+        let mut body = String::with_capacity(source.len());
+        for line in source.lines() {
+            let directive = line.trim_start();
+            if directive.starts_with("#extension") {
+                header.push(directive.to_owned());
+            } else if !directive.starts_with("#version") {
+                body.push_str(line);
+            }
+            body.push('\n');
+        }
+
+        if self == Self::Glsl300Es {
+            header.push("precision highp float;".to_owned());
+            header.push("precision highp int;".to_owned());
+        }
+
+        let mut writes_frag_color = false;
+        let body = map_identifiers(&body, |word| match (word, stage) {
+            ("attribute", ShaderType::Vertex) => Some("in"),
+            ("varying", ShaderType::Vertex) => Some("out"),
+            ("varying", ShaderType::Pixel) => Some("in"),
+            ("texture2D" | "textureCube", _) => Some("texture"),
+            ("gl_FragColor", ShaderType::Pixel) => {
+                writes_frag_color = true;
+                Some(FRAG_COLOR)
+            }
+            _ => None,
+        });
+        if writes_frag_color {
+            header.push(format!("out vec4 {FRAG_COLOR};"));
+        }
+
+        let offset = header.len() as u32;
+        let mut translated = header.join("\n");
+        translated.push('\n');
+        translated.push_str(&body);
+        (translated, offset)
+    }
+}
+
+/// Replace the identifiers of `source` that `f` maps to something else,
+/// leaving comments and numbers as they are.
+fn map_identifiers(source: &str, mut f: impl FnMut(&str) -> Option<&'static str>) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        let len = if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            comment.find("*/").map_or(rest.len(), |end| end + 4)
+        } else if c.is_ascii_alphanumeric() || c == '_' {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            let word = &rest[..len];
+            if !c.is_ascii_digit() {
+                if let Some(replacement) = f(word) {
+                    out.push_str(replacement);
+                    rest = &rest[len..];
+                    continue;
+                }
+            }
+            len
+        } else {
+            c.len_utf8()
+        };
+        out.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GlslDialect;
+    use crate::ShaderType;
+
+    const LEGACY_VERTEX: &str = "\
+#version 120
+attribute vec2 a_position; // the attribute
+varying vec2 v_uv;
+void main() {
+    v_uv = a_position * 0.5e1;
+    gl_Position = vec4(a_position, 0.0, 1.0);
+}
+";
+
+    const LEGACY_FRAGMENT: &str = "\
+#extension GL_OES_standard_derivatives : enable
+varying vec2 v_uv;
+uniform sampler2D u_texture;
+/* gl_FragColor in a comment */
+void main() {
+    gl_FragColor = texture2D(u_texture, v_uv);
+}
+";
+
+    #[test]
+    fn translate_legacy_syntax() {
+        let (vertex, offset) =
+            GlslDialect::Glsl330.translate_with_offset(LEGACY_VERTEX, ShaderType::Vertex);
+        assert_eq!(offset, 1);
+        assert_eq!(
+            vertex,
+            "\
+#version 330 core
+
+in vec2 a_position; // the attribute
+out vec2 v_uv;
+void main() {
+    v_uv = a_position * 0.5e1;
+    gl_Position = vec4(a_position, 0.0, 1.0);
+}
+"
+        );
+
+        let (fragment, offset) =
+            GlslDialect::Glsl300Es.translate_with_offset(LEGACY_FRAGMENT, ShaderType::Pixel);
+        assert_eq!(offset, 5);
+        assert_eq!(
+            fragment,
+            "\
+#version 300 es
+#extension GL_OES_standard_derivatives : enable
+precision highp float;
+precision highp int;
+out vec4 xd_FragColor;
+
+in vec2 v_uv;
+uniform sampler2D u_texture;
+/* gl_FragColor in a comment */
+void main() {
+    xd_FragColor = texture(u_texture, v_uv);
+}
+"
+        );
+        // the original line 6 is where `main` writes the color
+        assert!(fragment
+            .lines()
+            .nth(5 + 6 - 1)
+            .unwrap()
+            .contains("xd_FragColor ="));
+    }
+
+    #[test]
+    fn modern_sources_keep_their_outputs() {
+        let source = "#version 330 core\nin vec4 v_color;\nout vec4 o_color;\n\
+                      void main() { o_color = v_color; }\n";
+        let es = GlslDialect::Glsl300Es.translate(source, ShaderType::Pixel);
+        assert!(es.starts_with("#version 300 es\nprecision highp float;"));
+        assert!(!es.contains("xd_FragColor"));
+        assert!(es.ends_with(
+            "\nin vec4 v_color;\nout vec4 o_color;\nvoid main() { o_color = v_color; }\n"
+        ));
+
+        let core = GlslDialect::Glsl330.translate(source, ShaderType::Pixel);
+        assert_eq!(core, format!("#version 330 core\n\n{}", &source[18..]));
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{Color, Mat2x3, Pos2, Vec2};
+use std::f32::consts::TAU;
+
+/// Upper bound for the number of stops in a [`Gradient`], the GL path passes
+/// them to the shader as uniform arrays of this size.
+pub const MAX_GRADIENT_STOPS: usize = 16;
+
+/// The shape of a [`Gradient`], in local coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum GradientKind {
+    /// Changes along the line from `start` (offset 0) to `end` (offset 1).
+    Linear { start: Pos2, end: Pos2 },
+    /// Changes with the distance from `center`, reaching offset 1 at `radius`.
+    Radial { center: Pos2, radius: f32 },
+    /// Changes with the angle around `center`, going clockwise from offset 0 at
+    /// `angle` radians to offset 1 after a full turn.
+    Conic { center: Pos2, angle: f32 },
+}
+
+/// How a [`Gradient`] continues outside of the `[0, 1]` offset range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum GradientExtend {
+    /// Keep the color of the nearest end.
+    #[default]
+    Pad,
+    /// Start over from offset 0.
+    Repeat,
+    /// Go back and forth, mirroring every other repetition.
+    Reflect,
+}
+
+impl GradientExtend {
+    /// Map an offset into `[0, 1]`.
+    #[inline]
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Pad => t.clamp(0.0, 1.0),
+            Self::Repeat => t - t.floor(),
+            Self::Reflect => 1.0 - ((t - 2.0 * (t * 0.5).floor()) - 1.0).abs(),
+        }
+    }
+}
+
+/// A color gradient, drawn by the shapes queued while it's set with
+/// [`Painter::set_gradient`](crate::Painter::set_gradient).
+///
+/// Colors are interpolated between the stops without premultiplying alpha.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Gradient {
+    pub kind: GradientKind,
+    stops: Vec<(f32, Color)>,
+    pub extend: GradientExtend,
+}
+
+impl Gradient {
+    /// A gradient with the given stops, which are sorted by offset.
+    ///
+    /// Only the [`MAX_GRADIENT_STOPS`] stops with the lowest offsets are kept.
+    pub fn new(kind: GradientKind, stops: impl IntoIterator<Item = (f32, Color)>) -> Self {
+        let mut stops: Vec<_> = stops.into_iter().collect();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        if stops.len() > MAX_GRADIENT_STOPS {
+            log::warn!(
+                "gradient has {} stops, only the first {MAX_GRADIENT_STOPS} are used",
+                stops.len()
+            );
+            stops.truncate(MAX_GRADIENT_STOPS);
+        }
+        Self {
+            kind,
+            stops,
+            extend: GradientExtend::Pad,
+        }
+    }
+
+    #[inline]
+    pub fn linear(start: Pos2, end: Pos2, stops: impl IntoIterator<Item = (f32, Color)>) -> Self {
+        Self::new(GradientKind::Linear { start, end }, stops)
+    }
+
+    #[inline]
+    pub fn radial(
+        center: Pos2,
+        radius: f32,
+        stops: impl IntoIterator<Item = (f32, Color)>,
+    ) -> Self {
+        Self::new(GradientKind::Radial { center, radius }, stops)
+    }
+
+    #[inline]
+    pub fn conic(center: Pos2, angle: f32, stops: impl IntoIterator<Item = (f32, Color)>) -> Self {
+        Self::new(GradientKind::Conic { center, angle }, stops)
+    }
+
+    /// `(offset, color)` pairs, sorted by offset. Equal offsets make a hard edge.
+    #[inline]
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
+    }
+
+    #[inline]
+    pub fn with_extend(self, extend: GradientExtend) -> Self {
+        Self { extend, ..self }
+    }
+
+    /// Offset at local position `pos`, before applying [`Gradient::extend`].
+    ///
+    /// It's 1 everywhere for a linear gradient with `start == end` and for a
+    /// radial gradient without a positive radius, like in the GL shader.
+    pub fn offset(&self, pos: Pos2) -> f32 {
+        match self.kind {
+            GradientKind::Linear { start, end } => {
+                let d = end - start;
+                if d.length_sq() == 0.0 {
+                    return 1.0;
+                }
+                (pos - start).dot(d) / d.length_sq()
+            }
+            GradientKind::Radial { radius, .. } if radius <= 0.0 => 1.0,
+            GradientKind::Radial { center, radius } => (pos - center).length() / radius,
+            GradientKind::Conic { center, angle } => {
+                let t = ((pos - center).angle() - angle) / TAU;
+                t - t.floor()
+            }
+        }
+    }
+
+    /// Normalized RGBA color at local position `pos`.
+    pub fn color_at(&self, pos: Pos2) -> [f32; 4] {
+        let t = self.extend.apply(self.offset(pos));
+        let rgba = |c: Color| [c.r, c.g, c.b, c.a].map(|c| c as f32 / 255.0);
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return [0.0; 4];
+        };
+        if t <= first.0 {
+            return rgba(first.1);
+        }
+        for stops in self.stops.windows(2) {
+            let ((t0, c0), (t1, c1)) = (stops[0], stops[1]);
+            if t < t1 {
+                let f = (t - t0) / (t1 - t0);
+                let (c0, c1) = (rgba(c0), rgba(c1));
+                return std::array::from_fn(|i| c0[i] + (c1[i] - c0[i]) * f);
+            }
+        }
+        rgba(last.1)
+    }
+}
+
+/// A [`Gradient`] as used by the draws in [`Painter::gradients`](crate::Painter::gradients).
+#[derive(Clone, Debug, PartialEq)]
+pub struct GradientPaint {
+    pub gradient: Gradient,
+    /// Maps framebuffer pixels back to the local coordinates of the gradient.
+    pub inv_transform: Mat2x3,
+}
+
+impl GradientPaint {
+    /// Normalized RGBA color at `pos` in framebuffer pixels.
+    #[inline]
+    pub fn color_at(&self, pos: Vec2) -> [f32; 4] {
+        let local = self.inv_transform.mul_vec2(pos);
+        self.gradient.color_at(local.to_pos2())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gradient, GradientExtend, MAX_GRADIENT_STOPS};
+    use crate::{pos2, rect, rgb, vec2, Color, Painter, SoftwareRenderer};
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn extend_modes() {
+        let t = [-0.25, 0.25, 1.25, 2.75];
+        let apply = |extend: GradientExtend| t.map(|t| extend.apply(t));
+        assert_eq!(apply(GradientExtend::Pad), [0.0, 0.25, 1.0, 1.0]);
+        assert_eq!(apply(GradientExtend::Repeat), [0.75, 0.25, 0.25, 0.75]);
+        assert_eq!(apply(GradientExtend::Reflect), [0.25, 0.25, 0.75, 0.75]);
+
+        // equal offsets make a hard edge
+        let stops = [(0.0, Color::RED), (0.5, Color::RED), (0.5, Color::BLUE)];
+        let gradient = Gradient::linear(pos2(0.0, 0.0), pos2(1.0, 0.0), stops);
+        assert_eq!(gradient.color_at(pos2(0.49, 0.0)), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(gradient.color_at(pos2(0.5, 0.0)), [0.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn degenerate_gradients() {
+        // the lowest offsets are kept, whatever the order they're given in
+        let stops = (0..20).rev().map(|i| (i as f32 / 19.0, Color::RED));
+        let gradient = Gradient::linear(pos2(0.0, 0.0), pos2(1.0, 0.0), stops);
+        assert_eq!(gradient.stops().len(), MAX_GRADIENT_STOPS);
+        assert_eq!(gradient.stops()[0].0, 0.0);
+        assert!(gradient.stops().windows(2).all(|s| s[0].0 <= s[1].0));
+
+        // zero length axes and radii use the last stop
+        let stops = [(0.0, Color::RED), (1.0, Color::BLUE)];
+        let p = pos2(3.0, 4.0);
+        let linear = Gradient::linear(p, p, stops);
+        let radial = Gradient::radial(p, 0.0, stops);
+        for gradient in [linear, radial] {
+            for pos in [p, pos2(0.0, 0.0)] {
+                assert_eq!(gradient.offset(pos), 1.0);
+                assert_eq!(gradient.color_at(pos), [0.0, 0.0, 1.0, 1.0]);
+            }
+        }
+    }
+
+    #[test]
+    fn gradients_under_transform() {
+        let mut painter = Painter::default();
+        painter.begin(16, 16);
+        painter.translate(vec2(8.0, 0.0));
+        // black to white over 8 pixels, under the translation
+        let stops = [(0.0, Color::BLACK), (1.0, Color::WHITE)];
+        let gradient = Gradient::linear(pos2(0.0, 0.0), pos2(8.0, 0.0), stops);
+        painter.set_gradient(Some(gradient.with_extend(GradientExtend::Reflect)));
+        painter.filled_rect(rect(-8.0, 0.0, 16.0, 8.0), Color::WHITE);
+
+        // quarters of a conic gradient around the center of the bottom half
+        painter.set_transform(crate::Mat2x3::IDENTITY);
+        let stops = [
+            (0.0, Color::RED),
+            (0.25, Color::RED),
+            (0.25, Color::LIME),
+            (0.5, Color::LIME),
+            (0.5, Color::BLUE),
+            (1.0, Color::BLUE),
+        ];
+        painter.set_gradient(Some(Gradient::conic(pos2(8.0, 12.0), FRAC_PI_2, stops)));
+        painter.filled_rect(rect(0.0, 8.0, 16.0, 8.0), Color::WHITE);
+
+        let mut fb = SoftwareRenderer::new(16, 16);
+        fb.render(&painter);
+        // pixel centers at x + 0.5, reflected around x = 8
+        let gray = |x: f32| {
+            let c = (x / 8.0 * 255.0).round() as u8;
+            rgb(c, c, c)
+        };
+        assert_eq!(fb.pixel(8, 0), gray(0.5));
+        assert_eq!(fb.pixel(11, 4), gray(3.5));
+        assert_eq!(fb.pixel(4, 4), gray(3.5));
+        // the conic gradient starts pointing down and goes clockwise
+        assert_eq!(fb.pixel(7, 15), Color::RED);
+        assert_eq!(fb.pixel(1, 11), Color::LIME);
+        assert_eq!(fb.pixel(8, 9), Color::BLUE);
+        assert_eq!(fb.pixel(15, 12), Color::BLUE);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{texture::check_rgba8_len, Color, XdResult};
+
+/// An RGBA8 image in CPU memory, row by row from the top-left corner.
+///
+/// Returned by framebuffer readbacks like [`Painter::read_pixels`](crate::Painter::read_pixels).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Wrap tightly packed RGBA8 pixels.
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> XdResult<Self> {
+        check_rgba8_len(width, height, &pixels)?;
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Decode a PNG, converting it to RGBA8.
+    pub fn from_png(bytes: &[u8]) -> XdResult<Self> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(
+            png::Transformations::EXPAND
+                | png::Transformations::ALPHA
+                | png::Transformations::STRIP_16,
+        );
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+        buf.truncate(info.buffer_size());
+
+        // the transformations leave either gray and alpha or RGBA
+        let pixels = match info.color_type {
+            png::ColorType::GrayscaleAlpha => buf
+                .chunks_exact(2)
+                .flat_map(|p| [p[0], p[0], p[0], p[1]])
+                .collect(),
+            _ => buf,
+        };
+        Self::new(info.width, info.height, pixels)
+    }
+
+    #[inline]
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// RGBA8 pixels, row by row from the top-left corner.
+    #[inline]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    #[inline]
+    pub fn into_pixels(self) -> Vec<u8> {
+        self.pixels
+    }
+
+    /// Color of the pixel at `(x, y)`, counting from the top-left corner.
+    #[inline]
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        let p = &self.pixels[i..i + 4];
+        Color::from_rgba(p[0], p[1], p[2], p[3])
+    }
+
+    /// Reverse the order of the rows, for readbacks from a bottom-left origin.
+    pub(crate) fn flip_rows(&mut self) {
+        let row_len = self.width as usize * 4;
+        let rows = self.height as usize;
+        for y in 0..rows / 2 {
+            let (top, bottom) = self.pixels.split_at_mut((rows - 1 - y) * row_len);
+            top[y * row_len..(y + 1) * row_len].swap_with_slice(&mut bottom[..row_len]);
+        }
+    }
+
+    /// Encode the image as a PNG.
+    pub fn to_png(&self) -> XdResult<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        Ok(bytes)
+    }
+
+    /// Encode the image as a PNG and write it to `path`.
+    pub fn save_png(&self, path: impl AsRef<std::path::Path>) -> XdResult<()> {
+        std::fs::write(path, self.to_png()?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Image;
+    use crate::{rect, Color, Painter, SoftwareRenderer};
+
+    #[test]
+    fn readback_and_png_roundtrip() {
+        let mut painter = Painter::default();
+        painter.begin(8, 6);
+        painter.clear(Color::BLACK);
+        painter.filled_rect(rect(2.0, 1.0, 3.0, 2.0), Color::RED);
+        let mut fb = SoftwareRenderer::new(8, 6);
+        fb.render(&painter);
+
+        // clamped to the framebuffer
+        let image = fb.read_pixels(rect(2.0, 1.0, 10.0, 2.4));
+        assert_eq!((image.width(), image.height()), (6, 2));
+        assert_eq!(image.pixel(0, 0), Color::RED);
+        assert_eq!(image.pixel(2, 1), Color::RED);
+        assert_eq!(image.pixel(3, 0), Color::BLACK);
+
+        let png = image.to_png().unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+        assert_eq!(Image::from_png(&png).unwrap(), image);
+
+        let mut flipped = fb.read_pixels(rect(0.0, 0.0, 8.0, 6.0));
+        flipped.flip_rows();
+        assert_eq!(flipped.pixel(2, 4), Color::RED);
+        assert_eq!(flipped.pixel(2, 1), Color::BLACK);
+        flipped.flip_rows();
+        assert_eq!(flipped.pixels(), fb.pixels());
+    }
+}
@@ -1,29 +1,60 @@
 mod app;
 mod backend;
+mod blend;
 mod color;
 mod error;
+mod fill;
+mod glsl;
+mod gradient;
+mod image;
 mod mat;
 mod math;
+mod nine_slice;
 mod painter;
+mod path;
 mod pos2;
 mod rect;
+mod render_target;
 mod rot2;
 mod shader;
+mod shader_file;
+mod shapes;
+mod software;
+mod sprite;
+mod stroke;
+mod text;
+mod texture;
+mod uniform;
 mod vec2;
 mod window_settings;
 mod xd;
 
 pub use app::*;
 pub use backend::*;
+pub use blend::*;
 pub use color::*;
 pub use error::*;
+pub use fill::*;
+pub use glsl::*;
+pub use gradient::*;
+pub use image::*;
 pub use mat::*;
 pub use math::*;
+pub use nine_slice::*;
 pub use painter::*;
+pub use path::*;
 pub use pos2::*;
 pub use rect::*;
+pub use render_target::*;
 pub use rot2::*;
 pub use shader::*;
+pub use shader_file::*;
+pub use software::*;
+pub use sprite::*;
+pub use stroke::*;
+pub use text::*;
+pub use texture::*;
+pub use uniform::*;
 pub use vec2::*;
 pub use window_settings::*;
 pub use xd::*;
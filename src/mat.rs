@@ -1,9 +1,9 @@
 use std::ops::Mul;
 
-use crate::{vec2, Vec2};
+use crate::{vec2, Rot2, Vec2};
 
 #[repr(C)]
-#[derive(Clone, Copy, Default, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Mat2x3 {
     v: [[f32; 3]; 2],
@@ -21,6 +21,25 @@ impl Mat2x3 {
         }
     }
 
+    /// Translation by `offset`.
+    #[inline]
+    pub const fn translation(offset: Vec2) -> Self {
+        Self::new([1.0, 0.0, offset.x], [0.0, 1.0, offset.y])
+    }
+
+    /// Rotation (and scaling, if not normalized) around the origin.
+    #[inline]
+    pub fn rotation(rot: Rot2) -> Self {
+        let (x_axis, y_axis) = (rot * Vec2::X, rot * Vec2::Y);
+        Self::new([x_axis.x, y_axis.x, 0.0], [x_axis.y, y_axis.y, 0.0])
+    }
+
+    /// Non-uniform scale around the origin.
+    #[inline]
+    pub const fn scale(scale: Vec2) -> Self {
+        Self::new([scale.x, 0.0, 0.0], [0.0, scale.y, 0.0])
+    }
+
     /// Matrix for converting the screen coordinate system to the backend coordinate system.
     #[inline]
     pub fn default_proj(width: f32, height: f32) -> Self {
@@ -28,6 +47,9 @@ impl Mat2x3 {
     }
 
     /// Multiply the projection matrix (self) by the transform matrix.
+    ///
+    /// Only valid if `self` only scales and translates, like [`Mat2x3::default_proj`].
+    /// Use [`Mat2x3::mul_mat`] otherwise.
     #[inline]
     pub fn mul_transform(&self, transform: Self) -> Self {
         // this is an optimized way of doing this
@@ -46,6 +68,47 @@ impl Mat2x3 {
         )
     }
 
+    /// General matrix product, `self` is applied after `other`.
+    #[inline]
+    pub fn mul_mat(&self, other: Self) -> Self {
+        let (a, b) = (&self.v, &other.v);
+        Self::new(
+            [
+                a[0][0] * b[0][0] + a[0][1] * b[1][0],
+                a[0][0] * b[0][1] + a[0][1] * b[1][1],
+                a[0][0] * b[0][2] + a[0][1] * b[1][2] + a[0][2],
+            ],
+            [
+                a[1][0] * b[0][0] + a[1][1] * b[1][0],
+                a[1][0] * b[0][1] + a[1][1] * b[1][1],
+                a[1][0] * b[0][2] + a[1][1] * b[1][2] + a[1][2],
+            ],
+        )
+    }
+
+    /// The inverse transform, or `None` if the matrix collapses the plane.
+    pub fn inverse(&self) -> Option<Self> {
+        let [[a, b, c], [d, e, f]] = self.v;
+        let det = a * e - b * d;
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let (a, b, d, e) = (e / det, -b / det, -d / det, a / det);
+        Some(Self::new(
+            [a, b, -(a * c + b * f)],
+            [d, e, -(d * c + e * f)],
+        ))
+    }
+
+    /// Transform a direction, ignoring the translation.
+    #[inline]
+    pub fn mul_dir(&self, v: Vec2) -> Vec2 {
+        vec2(
+            self.v[0][0] * v.x + self.v[0][1] * v.y,
+            self.v[1][0] * v.x + self.v[1][1] * v.y,
+        )
+    }
+
     #[inline]
     pub fn mul_vec2(&self, v: Vec2) -> Vec2 {
         vec2(
@@ -54,6 +117,22 @@ impl Mat2x3 {
         )
     }
 
+    /// Expand into a column-major 3x3 matrix, as expected by GL `mat3` uniforms.
+    #[inline]
+    pub fn to_cols_array_3x3(&self) -> [f32; 9] {
+        [
+            self.v[0][0],
+            self.v[1][0],
+            0.0,
+            self.v[0][1],
+            self.v[1][1],
+            0.0,
+            self.v[0][2],
+            self.v[1][2],
+            1.0,
+        ]
+    }
+
     #[inline]
     pub fn transform_vec2s(&self, v: &mut [Vec2]) {
         v.iter_mut().for_each(|v| *v = self.mul_vec2(*v));
@@ -65,6 +144,25 @@ impl Mul for Mat2x3 {
 
     #[inline]
     fn mul(self, rhs: Self) -> Self::Output {
-        self.mul_transform(rhs)
+        self.mul_mat(rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mat2x3;
+    use crate::{vec2, Rot2};
+
+    #[test]
+    fn composition() {
+        let m = Mat2x3::translation(vec2(10.0, 20.0))
+            * Mat2x3::rotation(Rot2::from_angle(std::f32::consts::FRAC_PI_2))
+            * Mat2x3::scale(vec2(2.0, 3.0));
+        let v = m.mul_vec2(vec2(1.0, 1.0));
+        assert!((v - vec2(7.0, 22.0)).length() < 1e-5, "{v:?}");
+
+        // the optimized projection product agrees with the general one
+        let proj = Mat2x3::default_proj(640.0, 480.0);
+        assert!(proj.mul_transform(m) == proj.mul_mat(m));
     }
 }
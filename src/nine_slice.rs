@@ -0,0 +1,278 @@
+use crate::{vec2, Color, Painter, Rect, Texture, TextureId, Vec2};
+
+/// Widths of the borders around the center of a nine-slice.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Insets {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Insets {
+    /// The same width on every side.
+    #[inline]
+    pub const fn same(width: f32) -> Self {
+        Self {
+            left: width,
+            top: width,
+            right: width,
+            bottom: width,
+        }
+    }
+
+    /// `rect` shrunk by the insets.
+    #[inline]
+    pub fn shrink(&self, rect: Rect) -> Rect {
+        Rect::new(
+            rect.x + self.left,
+            rect.y + self.top,
+            rect.w - self.left - self.right,
+            rect.h - self.top - self.bottom,
+        )
+    }
+}
+
+impl From<f32> for Insets {
+    #[inline]
+    fn from(width: f32) -> Self {
+        Self::same(width)
+    }
+}
+
+/// How a region of a nine-slice fills its area.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum SliceMode {
+    /// Stretch the region over the area.
+    #[default]
+    Stretch,
+    /// Repeat the region at its size in texels, cutting off the last tile.
+    Tile,
+}
+
+/// Fill mode of each stretchable region of a nine-slice. Corners are never scaled.
+///
+/// The top and bottom edges only tile horizontally, the left and right edges
+/// only vertically.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct NineSliceModes {
+    pub top: SliceMode,
+    pub right: SliceMode,
+    pub bottom: SliceMode,
+    pub left: SliceMode,
+    pub center: SliceMode,
+}
+
+impl NineSliceModes {
+    pub const STRETCH: Self = Self::same(SliceMode::Stretch);
+    pub const TILE: Self = Self::same(SliceMode::Tile);
+
+    /// The same mode for every region.
+    #[inline]
+    pub const fn same(mode: SliceMode) -> Self {
+        Self {
+            top: mode,
+            right: mode,
+            bottom: mode,
+            left: mode,
+            center: mode,
+        }
+    }
+}
+
+/// Split the destination span `[dst, dst + dst_len]` into `(src, dst)` spans,
+/// one covering it all unless `tile` repeats the source span at its length.
+fn spans(src: f32, src_len: f32, dst: f32, dst_len: f32, tile: bool) -> Vec<[f32; 4]> {
+    if dst_len <= 0.0 || src_len <= 0.0 {
+        return Vec::new();
+    }
+    if !tile {
+        return vec![[src, src_len, dst, dst_len]];
+    }
+    let mut spans = Vec::new();
+    let mut offset = 0.0;
+    while offset < dst_len {
+        let len = src_len.min(dst_len - offset);
+        spans.push([src, len, dst + offset, len]);
+        offset += src_len;
+    }
+    spans
+}
+
+impl Painter {
+    /// Draw the `src` region of `texture` over `dst`, stretching the edges and
+    /// center while keeping the corners at their size, tinted by `color`.
+    ///
+    /// `insets` are the corner sizes in texels of `src`, and in local units on `dst`.
+    #[inline]
+    pub fn nine_slice(
+        &mut self,
+        texture: &Texture,
+        src: Rect,
+        insets: impl Into<Insets>,
+        dst: Rect,
+        color: impl Into<Color>,
+    ) {
+        let insets = insets.into();
+        let modes = NineSliceModes::STRETCH;
+        self.nine_slice_id_ex(texture.id(), texture.size(), src, insets, dst, modes, color);
+    }
+
+    /// Like [`Painter::nine_slice`], choosing between stretching and tiling
+    /// for each region.
+    #[inline]
+    pub fn nine_slice_ex(
+        &mut self,
+        texture: &Texture,
+        src: Rect,
+        insets: impl Into<Insets>,
+        dst: Rect,
+        modes: NineSliceModes,
+        color: impl Into<Color>,
+    ) {
+        let insets = insets.into();
+        self.nine_slice_id_ex(texture.id(), texture.size(), src, insets, dst, modes, color);
+    }
+
+    /// Same as [`Painter::nine_slice_ex`], for a texture given by id and size in texels.
+    ///
+    /// If `dst` is smaller than the corners, they shrink proportionally to fit.
+    #[allow(clippy::too_many_arguments)]
+    pub fn nine_slice_id_ex(
+        &mut self,
+        texture: TextureId,
+        texture_size: Vec2,
+        src: Rect,
+        insets: Insets,
+        dst: Rect,
+        modes: NineSliceModes,
+        color: impl Into<Color>,
+    ) {
+        let color = color.into();
+        let fit = |a: f32, b: f32, len: f32| {
+            let scale = if a + b > len { len / (a + b) } else { 1.0 };
+            (a * scale, b * scale)
+        };
+        let (left, right) = fit(insets.left, insets.right, dst.w.max(0.0));
+        let (top, bottom) = fit(insets.top, insets.bottom, dst.h.max(0.0));
+        let dst_insets = Insets {
+            left,
+            top,
+            right,
+            bottom,
+        };
+        let (src_center, dst_center) = (insets.shrink(src), dst_insets.shrink(dst));
+
+        // the three columns and rows as (src, src_len, dst, dst_len)
+        let columns = [
+            [src.x, insets.left, dst.x, left],
+            [src_center.x, src_center.w, dst_center.x, dst_center.w],
+            [src_center.max().x, insets.right, dst_center.max().x, right],
+        ];
+        let rows = [
+            [src.y, insets.top, dst.y, top],
+            [src_center.y, src_center.h, dst_center.y, dst_center.h],
+            [
+                src_center.max().y,
+                insets.bottom,
+                dst_center.max().y,
+                bottom,
+            ],
+        ];
+
+        let prev = self.texture.replace(texture);
+        for (j, row) in rows.into_iter().enumerate() {
+            for (i, column) in columns.into_iter().enumerate() {
+                // which axes of this region tile
+                let (tile_x, tile_y) = match (i, j) {
+                    (1, 0) => (modes.top == SliceMode::Tile, false),
+                    (1, 2) => (modes.bottom == SliceMode::Tile, false),
+                    (0, 1) => (false, modes.left == SliceMode::Tile),
+                    (2, 1) => (false, modes.right == SliceMode::Tile),
+                    (1, 1) => (
+                        modes.center == SliceMode::Tile,
+                        modes.center == SliceMode::Tile,
+                    ),
+                    _ => (false, false),
+                };
+                let [sx, sw, dx, dw] = column;
+                let [sy, sh, dy, dh] = row;
+                for [sy, sh, dy, dh] in spans(sy, sh, dy, dh, tile_y) {
+                    for [sx, sw, dx, dw] in spans(sx, sw, dx, dw, tile_x) {
+                        let (u0, u1) = (sx / texture_size.x, (sx + sw) / texture_size.x);
+                        let (v0, v1) = (sy / texture_size.y, (sy + sh) / texture_size.y);
+                        let (x1, y1) = (dx + dw, dy + dh);
+                        self.push_quad(
+                            [vec2(dx, y1), vec2(x1, y1), vec2(x1, dy), vec2(dx, dy)],
+                            [vec2(u0, v1), vec2(u1, v1), vec2(u1, v0), vec2(u0, v0)],
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+        self.queue_indexed_draw();
+        self.texture = prev;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Insets, NineSliceModes, SliceMode};
+    use crate::{rect, rgb, vec2, Color, Painter, SoftwareRenderer, TextureOptions};
+
+    #[test]
+    fn corners_edges_and_tiling() {
+        // a 4x4 texture with a distinct color per texel
+        let pixels: Vec<u8> = (0..16)
+            .flat_map(|i| [(i % 4) as u8 * 80, (i / 4) as u8 * 80, 255, 255])
+            .collect();
+        let texel = |x: u8, y: u8| rgb(x * 80, y * 80, 255);
+        let mut fb = SoftwareRenderer::new(8, 6);
+        let texture = fb
+            .create_texture(4, 4, &pixels, TextureOptions::NEAREST)
+            .unwrap();
+
+        let mut painter = Painter::default();
+        let src = rect(0.0, 0.0, 4.0, 4.0);
+        let modes = NineSliceModes {
+            top: SliceMode::Tile,
+            ..NineSliceModes::STRETCH
+        };
+        for (modes, top_edge) in [
+            (NineSliceModes::STRETCH, [1, 1, 1, 2, 2, 2]),
+            (modes, [1, 2, 1, 2, 1, 2]),
+        ] {
+            painter.begin(8, 6);
+            let dst = rect(0.0, 0.0, 8.0, 6.0);
+            let size = vec2(4.0, 4.0);
+            painter.nine_slice_id_ex(
+                texture,
+                size,
+                src,
+                Insets::same(1.0),
+                dst,
+                modes,
+                Color::WHITE,
+            );
+            fb.render(&painter);
+
+            // unscaled corners
+            assert_eq!(fb.pixel(0, 0), texel(0, 0));
+            assert_eq!(fb.pixel(7, 0), texel(3, 0));
+            assert_eq!(fb.pixel(0, 5), texel(0, 3));
+            assert_eq!(fb.pixel(7, 5), texel(3, 3));
+            for (x, &u) in top_edge.iter().enumerate() {
+                assert_eq!(fb.pixel(x as u32 + 1, 0), texel(u, 0), "{modes:?} x = {x}");
+            }
+            // the stretched left edge and center, 4 pixels for 2 texels
+            assert_eq!(fb.pixel(0, 2), texel(0, 1));
+            assert_eq!(fb.pixel(0, 3), texel(0, 2));
+            assert_eq!(fb.pixel(3, 2), texel(1, 1));
+            assert_eq!(fb.pixel(4, 3), texel(2, 2));
+        }
+    }
+}
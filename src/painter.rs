@@ -1,16 +1,201 @@
-use crate::{vec2, Color, Mat2x3, Rect, Vec2};
+use crate::{
+    shader::create_program, text::GlyphAtlas, uniform::UniformCache, vec2, BlendMode, Color, Font,
+    Gradient, GradientExtend, GradientKind, GradientPaint, Image, Mat2x3, Rect, RenderTarget,
+    RenderTargetFormat, RenderTargetId, Rot2, Shader, ShaderId, ShaderType, Texture, TextureId,
+    UniformValue, Uniforms, Vec2,
+};
 use glow::{
-    Context, HasContext, NativeBuffer, VertexArray, ARRAY_BUFFER, BLEND, CULL_FACE, DEPTH_TEST,
-    FUNC_ADD, ONE, ONE_MINUS_SRC_ALPHA, SCISSOR_TEST, SRC_ALPHA, STENCIL_TEST,
+    Buffer, Context, HasContext, PixelPackData, Program, UniformLocation, VertexArray,
+    ARRAY_BUFFER, BLEND, COLOR_BUFFER_BIT, CULL_FACE, DEPTH_TEST, ELEMENT_ARRAY_BUFFER, FLOAT,
+    FRAMEBUFFER, FUNC_ADD, LINES, LINE_STRIP, NEAREST, PACK_ALIGNMENT, POINTS, RGBA, RGBA8,
+    SCISSOR_TEST, STENCIL_TEST, STREAM_DRAW, TEXTURE0, TEXTURE_2D, TEXTURE_MAG_FILTER,
+    TEXTURE_MIN_FILTER, TRIANGLES, TRIANGLE_STRIP, UNSIGNED_BYTE, UNSIGNED_SHORT,
 };
+use std::collections::HashMap;
 
 const DEFAULT_MAX_VERTICES: usize = 65536;
 const DEFAULT_MAX_COMMANDS: usize = 16384;
 
+/// Number of vertices a single indexed batch can address with `u16` indices.
+pub const MAX_BATCH_VERTICES: usize = u16::MAX as usize + 1;
+
+/// Maximum distance in pixels between a curve and its tessellation.
+pub const CURVE_TOLERANCE: f32 = 0.25;
+/// Upper bound for the number of segments in a single arc or curve.
+pub(crate) const MAX_CURVE_SEGMENTS: usize = 1024;
+
+/// Number of segments needed to approximate an arc of `radius` spanning `angle`
+/// radians, so that it deviates less than `tolerance` from the true curve.
+pub(crate) fn arc_segments(radius: f32, angle: f32, tolerance: f32) -> usize {
+    let radius = radius.abs();
+    if radius <= tolerance || !radius.is_finite() {
+        return 1;
+    }
+    let step = 2.0 * (1.0 - tolerance / radius).acos();
+    ((angle.abs() / step).ceil() as usize).clamp(1, MAX_CURVE_SEGMENTS)
+}
+
+/// Vertex shader of the painter's default pipeline, also used by [`Shader::from_fragment`].
+pub const DEFAULT_VERTEX_SHADER: &str = r#"#version 330 core
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec2 a_texcoord;
+layout(location = 2) in vec4 a_color;
+
+uniform mat3 u_proj;
+
+out vec2 v_position;
+out vec2 v_texcoord;
+out vec4 v_color;
+
+void main() {
+    v_position = a_position;
+    v_texcoord = a_texcoord;
+    v_color = a_color;
+    gl_Position = vec4((u_proj * vec3(a_position, 1.0)).xy, 0.0, 1.0);
+}
+"#;
+
+/// Fragment shader of the painter's default pipeline, a starting point for custom ones.
+// the gradient math mirrors `Gradient::color_at`, so the CPU renderer matches
+pub const DEFAULT_FRAGMENT_SHADER: &str = r#"#version 330 core
+in vec2 v_position;
+in vec2 v_texcoord;
+in vec4 v_color;
+
+uniform sampler2D u_texture;
+// whether colors and textures are already multiplied by alpha
+uniform bool u_premultiplied;
+
+// 0 = none, 1 = linear, 2 = radial, 3 = conic
+uniform int u_gradient_kind;
+// 0 = pad, 1 = repeat, 2 = reflect
+uniform int u_gradient_extend;
+// framebuffer pixels to gradient space
+uniform mat3 u_gradient_inv;
+// linear: start, end. radial: center, radius. conic: center, angle
+uniform vec4 u_gradient_params;
+uniform int u_stop_count;
+uniform float u_stop_offsets[16];
+uniform vec4 u_stop_colors[16];
+
+out vec4 o_color;
+
+const float TAU = 6.28318530718;
+
+vec4 gradient_color() {
+    if (u_stop_count == 0) {
+        return vec4(0.0);
+    }
+
+    vec2 p = (u_gradient_inv * vec3(v_position, 1.0)).xy;
+    vec2 d = p - u_gradient_params.xy;
+    float t;
+    if (u_gradient_kind == 1) {
+        vec2 axis = u_gradient_params.zw - u_gradient_params.xy;
+        // like `Gradient::offset`, degenerate gradients are at offset 1
+        t = dot(axis, axis) == 0.0 ? 1.0 : dot(d, axis) / dot(axis, axis);
+    } else if (u_gradient_kind == 2) {
+        t = u_gradient_params.z <= 0.0 ? 1.0 : length(d) / u_gradient_params.z;
+    } else {
+        t = fract((atan(d.y, d.x) - u_gradient_params.z) / TAU);
+    }
+
+    if (u_gradient_extend == 0) {
+        t = clamp(t, 0.0, 1.0);
+    } else if (u_gradient_extend == 1) {
+        t = fract(t);
+    } else {
+        t = 1.0 - abs(mod(t, 2.0) - 1.0);
+    }
+
+    if (t <= u_stop_offsets[0]) {
+        return u_stop_colors[0];
+    }
+    for (int i = 1; i < u_stop_count; i++) {
+        if (t < u_stop_offsets[i]) {
+            float f = (t - u_stop_offsets[i - 1]) / (u_stop_offsets[i] - u_stop_offsets[i - 1]);
+            return mix(u_stop_colors[i - 1], u_stop_colors[i], f);
+        }
+    }
+    return u_stop_colors[u_stop_count - 1];
+}
+
+void main() {
+    o_color = v_color * texture(u_texture, v_texcoord);
+    // every blend mode expects premultiplied alpha
+    if (!u_premultiplied) {
+        o_color.rgb *= o_color.a;
+    }
+    if (u_gradient_kind != 0) {
+        vec4 paint = gradient_color();
+        o_color *= vec4(paint.rgb * paint.a, paint.a);
+    }
+}
+"#;
+
+/// Set the GL blend equation and factors for `blend_mode`.
+///
+/// # Safety
+///
+/// `gl` must be the current GL context.
+unsafe fn set_blend_mode(gl: &Context, blend_mode: BlendMode) {
+    let (equation, [src_rgb, dst_rgb, src_alpha, dst_alpha]) = blend_mode.to_gl();
+    gl.blend_equation_separate(equation, FUNC_ADD);
+    gl.blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+}
+
+/// Point the vertex attributes of the bound vertex array at the [`Vertex`] layout
+/// in the bound vertex buffer, starting at vertex `base_vertex`.
+///
+/// # Safety
+///
+/// `gl` must be the current GL context, with the vertex array and buffer bound.
+unsafe fn set_vertex_layout(gl: &Context, base_vertex: u32) {
+    let stride = std::mem::size_of::<Vertex>() as i32;
+    let base = base_vertex as i32 * stride;
+    gl.vertex_attrib_pointer_f32(
+        0,
+        2,
+        FLOAT,
+        false,
+        stride,
+        base + std::mem::offset_of!(Vertex, position) as i32,
+    );
+    gl.vertex_attrib_pointer_f32(
+        1,
+        2,
+        FLOAT,
+        false,
+        stride,
+        base + std::mem::offset_of!(Vertex, texcoord) as i32,
+    );
+    gl.vertex_attrib_pointer_f32(
+        2,
+        4,
+        UNSIGNED_BYTE,
+        true,
+        stride,
+        base + std::mem::offset_of!(Vertex, color) as i32,
+    );
+}
+
 #[repr(C)]
+#[derive(Clone, Copy, PartialEq)]
 pub struct Vertex {
-    position: Vec2,
-    texcoord: Vec2,
+    pub position: Vec2,
+    pub texcoord: Vec2,
+    /// Vertex color, multiplied with the rest of the shading.
+    pub color: Color,
+}
+
+impl Default for Vertex {
+    fn default() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            texcoord: Vec2::ZERO,
+            color: Color::WHITE,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Default, PartialEq)]
@@ -23,6 +208,27 @@ pub enum PrimitiveType {
     LineStrip,
 }
 
+impl PrimitiveType {
+    /// Whether consecutive draws can be joined into one, because every primitive
+    /// is made of its own vertices, unlike strips.
+    #[inline]
+    pub const fn is_list(self) -> bool {
+        matches!(self, Self::Triangles | Self::Points | Self::Lines)
+    }
+
+    /// The matching GL primitive mode.
+    #[inline]
+    pub const fn to_gl(self) -> u32 {
+        match self {
+            Self::Triangles => TRIANGLES,
+            Self::Points => POINTS,
+            Self::Lines => LINES,
+            Self::TriangleStrip => TRIANGLE_STRIP,
+            Self::LineStrip => LINE_STRIP,
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum Command {
     None,
@@ -30,24 +236,318 @@ pub enum Command {
     Clear(Color),
     /// Scissor the viewport to the given rectangle.
     Clip(Rect),
+    /// Render the following commands into `target`, or into the window for `None`.
+    ///
+    /// Resets the viewport and scissor to cover the whole `width` x `height` target.
+    Target {
+        target: Option<RenderTargetId>,
+        width: u32,
+        height: u32,
+    },
+    /// Draw the following commands with `shader`, or the default shader for `None`.
+    Shader(Option<ShaderId>),
     /// Drawcall.
     Draw {
         prim: PrimitiveType,
         vertex_index: u32,
         num_vertices: u32,
+        /// Texture to sample from, or `None` for a plain white texture.
+        texture: Option<TextureId>,
+        /// Index into [`Painter::gradients`] multiplied with the color, if any.
+        gradient: Option<u32>,
+        blend_mode: BlendMode,
+        /// Index into [`Painter::uniforms`] set on the shader before drawing, if any.
+        uniforms: Option<u32>,
     },
+    /// Indexed drawcall, reading `num_indices` from [`Painter::indices`].
+    ///
+    /// Indices are relative to `base_vertex`, so a batch can address up to
+    /// [`MAX_BATCH_VERTICES`] vertices with `u16` indices.
+    DrawIndexed {
+        prim: PrimitiveType,
+        base_vertex: u32,
+        index_offset: u32,
+        num_indices: u32,
+        /// Texture to sample from, or `None` for a plain white texture.
+        texture: Option<TextureId>,
+        /// Index into [`Painter::gradients`] multiplied with the color, if any.
+        gradient: Option<u32>,
+        blend_mode: BlendMode,
+        /// Index into [`Painter::uniforms`] set on the shader before drawing, if any.
+        uniforms: Option<u32>,
+    },
+}
+
+/// Locations of the gradient uniforms in the default shader.
+#[derive(Default)]
+struct GradientUniforms {
+    kind: Option<UniformLocation>,
+    extend: Option<UniformLocation>,
+    inv_transform: Option<UniformLocation>,
+    params: Option<UniformLocation>,
+    stop_count: Option<UniformLocation>,
+    stop_offsets: Option<UniformLocation>,
+    stop_colors: Option<UniformLocation>,
+}
+
+impl GradientUniforms {
+    unsafe fn new(gl: &Context, program: Program) -> Self {
+        let location = |name| gl.get_uniform_location(program, name);
+        Self {
+            kind: location("u_gradient_kind"),
+            extend: location("u_gradient_extend"),
+            inv_transform: location("u_gradient_inv"),
+            params: location("u_gradient_params"),
+            stop_count: location("u_stop_count"),
+            stop_offsets: location("u_stop_offsets"),
+            stop_colors: location("u_stop_colors"),
+        }
+    }
+
+    /// Upload `paint`, or turn gradients off for `None`.
+    unsafe fn set(&self, gl: &Context, paint: Option<&GradientPaint>) {
+        let Some(paint) = paint else {
+            gl.uniform_1_i32(self.kind.as_ref(), 0);
+            return;
+        };
+        let gradient = &paint.gradient;
+        let (kind, params) = match gradient.kind {
+            GradientKind::Linear { start, end } => (1, [start.x, start.y, end.x, end.y]),
+            GradientKind::Radial { center, radius } => (2, [center.x, center.y, radius, 0.0]),
+            GradientKind::Conic { center, angle } => (3, [center.x, center.y, angle, 0.0]),
+        };
+        let extend = match gradient.extend {
+            GradientExtend::Pad => 0,
+            GradientExtend::Repeat => 1,
+            GradientExtend::Reflect => 2,
+        };
+        let offsets: Vec<f32> = gradient.stops().iter().map(|s| s.0).collect();
+        let colors: Vec<f32> = gradient
+            .stops()
+            .iter()
+            .flat_map(|(_, c)| [c.r, c.g, c.b, c.a].map(|c| c as f32 / 255.0))
+            .collect();
+
+        gl.uniform_1_i32(self.kind.as_ref(), kind);
+        gl.uniform_1_i32(self.extend.as_ref(), extend);
+        gl.uniform_matrix_3_f32_slice(
+            self.inv_transform.as_ref(),
+            false,
+            &paint.inv_transform.to_cols_array_3x3(),
+        );
+        gl.uniform_4_f32_slice(self.params.as_ref(), &params);
+        gl.uniform_1_i32(self.stop_count.as_ref(), offsets.len() as i32);
+        if !offsets.is_empty() {
+            gl.uniform_1_f32_slice(self.stop_offsets.as_ref(), &offsets);
+            gl.uniform_4_f32_slice(self.stop_colors.as_ref(), &colors);
+        }
+    }
+}
+
+/// Draw call counts for the current frame, see [`Painter::stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrawStats {
+    /// Draws queued by shapes and [`Painter::queue_draw`].
+    pub queued_draws: usize,
+    /// Queued draws that were merged into an earlier draw instead of recording
+    /// a new command, including the ones merged by [`Painter::sort_commands`].
+    pub merged_draws: usize,
+    /// Draw commands left for the backend to issue.
+    pub draw_calls: usize,
+    /// All recorded commands, including clears and clips.
+    pub commands: usize,
+}
+
+/// Bounding rect of `positions`, zero sized at the origin if there are none.
+fn bounds(mut positions: impl Iterator<Item = Vec2>) -> Rect {
+    let first = positions.next().unwrap_or(Vec2::ZERO);
+    let (min, max) = positions.fold((first, first), |(min, max), p| (min.min(p), max.max(p)));
+    Rect::from_min_max(min.to_pos2(), max.to_pos2())
+}
+
+/// A draw being moved around by [`Painter::sort_commands`].
+struct SortedDraw {
+    command: Command,
+    /// Bounds of the vertices in framebuffer pixels.
+    bounds: Rect,
+    /// Ranges of [`Painter::indices`] drawn by an indexed draw, in order.
+    index_ranges: Vec<std::ops::Range<usize>>,
 }
 
+impl SortedDraw {
+    fn new(command: Command, vertices: &[Vertex], indices: &[u16]) -> Self {
+        match command {
+            Command::Draw {
+                vertex_index,
+                num_vertices,
+                ..
+            } => {
+                let range = vertex_index as usize..(vertex_index + num_vertices) as usize;
+                Self {
+                    command,
+                    bounds: bounds(vertices[range].iter().map(|v| v.position)),
+                    index_ranges: Vec::new(),
+                }
+            }
+            Command::DrawIndexed {
+                base_vertex,
+                index_offset,
+                num_indices,
+                ..
+            } => {
+                let range = index_offset as usize..(index_offset + num_indices) as usize;
+                let positions = indices[range.clone()]
+                    .iter()
+                    .map(|&i| vertices[base_vertex as usize + i as usize].position);
+                Self {
+                    command,
+                    bounds: bounds(positions),
+                    index_ranges: vec![range],
+                }
+            }
+            _ => unreachable!("only draws are sorted"),
+        }
+    }
+
+    /// Whether `other` can be drawn as part of this draw.
+    fn can_merge(&self, other: &Self) -> bool {
+        match (self.command, other.command) {
+            (
+                Command::DrawIndexed {
+                    prim,
+                    base_vertex,
+                    texture,
+                    gradient,
+                    blend_mode,
+                    uniforms,
+                    ..
+                },
+                Command::DrawIndexed {
+                    prim: other_prim,
+                    base_vertex: other_base_vertex,
+                    texture: other_texture,
+                    gradient: other_gradient,
+                    blend_mode: other_blend_mode,
+                    uniforms: other_uniforms,
+                    ..
+                },
+            ) => {
+                prim.is_list()
+                    && prim == other_prim
+                    && base_vertex == other_base_vertex
+                    && texture == other_texture
+                    && gradient == other_gradient
+                    && blend_mode == other_blend_mode
+                    && uniforms == other_uniforms
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the draws may cover the same pixel. Touching bounds count, since
+    /// a pixel center on the shared edge may belong to either draw.
+    fn overlaps(&self, other: &Self) -> bool {
+        let (a, b) = (self.bounds, other.bounds);
+        a.x <= b.x + b.w && b.x <= a.x + a.w && a.y <= b.y + b.h && b.y <= a.y + a.h
+    }
+}
+
+/// GL state while [`Painter::gl_render`] replays the commands, `None` where unknown.
+struct RenderState {
+    shader: Option<ShaderId>,
+    program: Option<Program>,
+    proj_location: Option<UniformLocation>,
+    premultiplied_location: Option<UniformLocation>,
+    texture: Option<Option<TextureId>>,
+    gradient: Option<Option<u32>>,
+    blend_mode: Option<BlendMode>,
+    uniforms: Option<u32>,
+}
+
+/// Painter state saved by [`Painter::push_render_target`].
+struct TargetState {
+    target: Option<RenderTargetId>,
+    width: u32,
+    height: u32,
+    transform: Mat2x3,
+    transform_stack: Vec<Mat2x3>,
+    clip: Rect,
+    clip_stack: Vec<Rect>,
+}
+
+/// Records drawing commands for a frame and replays them with GL.
+///
+/// Vertices are stored in framebuffer pixels, already multiplied by `transform`.
+/// The default shader applies `proj` on the GPU, so every vertex ends up
+/// transformed by `mvp`.
 pub struct Painter {
     pub width: u32,
     pub height: u32,
     pub proj: Mat2x3,
     pub transform: Mat2x3,
     pub mvp: Mat2x3,
+    /// Transforms saved by [`Painter::push_transform`].
+    transform_stack: Vec<Mat2x3>,
+    /// Effective clip rect in framebuffer pixels.
+    clip: Rect,
+    /// Clip rects saved by [`Painter::push_clip`].
+    clip_stack: Vec<Rect>,
+    /// Clip rect of the last [`Command::Clip`], the whole framebuffer at first.
+    issued_clip: Rect,
+    /// Render target of the following commands, `None` for the window.
+    target: Option<RenderTargetId>,
+    /// States saved by [`Painter::push_render_target`].
+    target_stack: Vec<TargetState>,
+    /// Shader of the following draws, `None` for the default shader.
+    shader: Option<ShaderId>,
+    /// Shaders saved by [`Painter::push_shader`].
+    shader_stack: Vec<Option<ShaderId>>,
+    /// Shader of the last [`Command::Shader`], the default shader at first.
+    issued_shader: Option<ShaderId>,
     pub commands: Vec<Command>,
     pub vertices: Vec<Vertex>,
+    /// Indices of [`Command::DrawIndexed`], relative to the base vertex of their batch.
+    pub indices: Vec<u16>,
+    /// First vertex of the current indexed batch.
+    batch_vertex: usize,
+    /// First index that isn't part of an indexed draw yet.
+    draw_index: usize,
+    /// Draw call counts for the current frame.
+    stats: DrawStats,
+    /// Texture bound to the following draws.
+    pub texture: Option<TextureId>,
+    /// Gradient applied to the following draws.
+    gradient: Option<Gradient>,
+    /// Gradients referenced by [`Command::Draw`] and [`Command::DrawIndexed`].
+    pub gradients: Vec<GradientPaint>,
+    /// Blend mode of the following draws.
+    pub blend_mode: BlendMode,
+    /// Uniforms set before the following draws.
+    draw_uniforms: Uniforms,
+    /// Uniforms referenced by [`Command::Draw`] and [`Command::DrawIndexed`].
+    pub uniforms: Vec<Uniforms>,
+    /// Font of the following text.
+    pub(crate) font: Option<Font>,
+    /// Rasterized glyphs, shared by all fonts.
+    pub(crate) glyph_atlas: GlyphAtlas,
     /// GL vertex buffer object.
-    vbo: Option<NativeBuffer>,
+    vbo: Option<Buffer>,
+    /// GL element buffer object holding `indices`.
+    ebo: Option<Buffer>,
+    /// GL vertex array object describing the [`Vertex`] layout.
+    vao: Option<VertexArray>,
+    /// Default shader program.
+    program: Option<Program>,
+    /// Location of `u_proj` in the default shader.
+    proj_location: Option<UniformLocation>,
+    /// Location of `u_premultiplied` in the default shader.
+    premultiplied_location: Option<UniformLocation>,
+    /// Locations of the gradient uniforms in the default shader.
+    gradient_uniforms: GradientUniforms,
+    /// Locations of the uniforms set by draws, per shader.
+    uniform_caches: HashMap<Option<ShaderId>, UniformCache>,
+    /// 1x1 white texture sampled by untextured draws.
+    white_texture: Option<TextureId>,
 }
 
 impl Default for Painter {
@@ -64,9 +564,38 @@ impl Painter {
             proj: Mat2x3::default(),
             transform: Mat2x3::IDENTITY,
             mvp: Mat2x3::default(),
+            transform_stack: Vec::new(),
+            clip: Rect::new(0.0, 0.0, width as f32, height as f32),
+            clip_stack: Vec::new(),
+            issued_clip: Rect::new(0.0, 0.0, width as f32, height as f32),
+            target: None,
+            target_stack: Vec::new(),
+            shader: None,
+            shader_stack: Vec::new(),
+            issued_shader: None,
             commands: Vec::with_capacity(num_commands),
             vertices: Vec::with_capacity(num_vertices),
+            indices: Vec::with_capacity(num_vertices * 3 / 2),
+            batch_vertex: 0,
+            draw_index: 0,
+            stats: DrawStats::default(),
+            texture: None,
+            gradient: None,
+            gradients: Vec::new(),
+            blend_mode: BlendMode::Alpha,
+            draw_uniforms: Uniforms::new(),
+            uniforms: Vec::new(),
+            font: None,
+            glyph_atlas: GlyphAtlas::default(),
             vbo: None,
+            ebo: None,
+            vao: None,
+            program: None,
+            proj_location: None,
+            premultiplied_location: None,
+            gradient_uniforms: GradientUniforms::default(),
+            uniform_caches: HashMap::new(),
+            white_texture: None,
         }
     }
 
@@ -74,92 +603,1213 @@ impl Painter {
         (self.width, self.height) = (width, height);
         self.commands.clear();
         self.vertices.clear();
+        self.indices.clear();
+        self.batch_vertex = 0;
+        self.draw_index = 0;
+        self.stats = DrawStats::default();
+        self.texture = None;
+        self.gradient = None;
+        self.gradients.clear();
+        self.blend_mode = BlendMode::Alpha;
+        self.draw_uniforms.clear();
+        self.uniforms.clear();
+        self.shader = None;
+        self.shader_stack.clear();
+        self.issued_shader = None;
+        self.glyph_atlas.begin_frame();
         self.proj = Mat2x3::default_proj(self.width as f32, self.height as f32);
         self.transform = Mat2x3::IDENTITY;
+        self.transform_stack.clear();
         self.mvp = self.proj;
+        self.clip = Rect::new(0.0, 0.0, width as f32, height as f32);
+        self.clip_stack.clear();
+        self.issued_clip = self.clip;
+        self.target = None;
+        self.target_stack.clear();
+    }
+
+    /// Save the current transform, to be restored by [`Painter::pop_transform`].
+    #[inline]
+    pub fn push_transform(&mut self) {
+        self.transform_stack.push(self.transform);
+    }
+
+    /// Restore the transform saved by the last [`Painter::push_transform`].
+    pub fn pop_transform(&mut self) {
+        match self.transform_stack.pop() {
+            Some(transform) => self.set_transform(transform),
+            None => log::warn!("pop_transform called without a matching push_transform"),
+        }
+    }
+
+    /// Replace the current transform.
+    #[inline]
+    pub fn set_transform(&mut self, transform: Mat2x3) {
+        self.transform = transform;
+        self.mvp = self.proj.mul_transform(transform);
+    }
+
+    /// Apply `transform` in local coordinates, before the current transform.
+    ///
+    /// Like on a canvas, transforms applied later act in the coordinates set up by
+    /// earlier ones: after `translate` then `rotate`, the rotation is around the new origin.
+    #[inline]
+    pub fn apply_transform(&mut self, transform: Mat2x3) {
+        self.set_transform(self.transform * transform);
+    }
+
+    /// Move the local origin by `offset`.
+    #[inline]
+    pub fn translate(&mut self, offset: Vec2) {
+        self.apply_transform(Mat2x3::translation(offset));
+    }
+
+    /// Rotate the local coordinate system around the local origin.
+    #[inline]
+    pub fn rotate(&mut self, rot: Rot2) {
+        self.apply_transform(Mat2x3::rotation(rot));
+    }
+
+    /// Scale the local coordinate system around the local origin.
+    #[inline]
+    pub fn scale(&mut self, scale: Vec2) {
+        self.apply_transform(Mat2x3::scale(scale));
+    }
+
+    /// Restrict the following clears and draws to `rect`, intersected with the
+    /// current clip rect, until the matching [`Painter::pop_clip`].
+    ///
+    /// `rect` is in local coordinates. Scissor rects are axis-aligned, so under a
+    /// rotation this clips to the bounding box of the transformed `rect`.
+    pub fn push_clip(&mut self, rect: Rect) {
+        let mut corners = [
+            rect.min().to_vec2(),
+            vec2(rect.x + rect.w, rect.y),
+            rect.max().to_vec2(),
+            vec2(rect.x, rect.y + rect.h),
+        ];
+        self.transform.transform_vec2s(&mut corners);
+        let (min, max) = corners[1..]
+            .iter()
+            .fold((corners[0], corners[0]), |(min, max), c| {
+                (min.min(*c), max.max(*c))
+            });
+
+        // snap to whole pixels, so changes that don't affect the scissor are skipped
+        let rect = Rect::from_min_max(min.round().to_pos2(), max.round().to_pos2());
+        self.clip_stack.push(self.clip);
+        self.clip = self.clip.intersect(&rect);
+    }
+
+    /// Restore the clip rect saved by the last [`Painter::push_clip`].
+    pub fn pop_clip(&mut self) {
+        match self.clip_stack.pop() {
+            Some(clip) => self.clip = clip,
+            None => log::warn!("pop_clip called without a matching push_clip"),
+        }
+    }
+
+    /// Effective clip rect in framebuffer pixels.
+    #[inline]
+    pub fn clip_rect(&self) -> Rect {
+        self.clip
+    }
+
+    /// Render the following commands into `target` until the matching
+    /// [`Painter::pop_render_target`].
+    ///
+    /// The target starts out with an identity transform and no clip, the current
+    /// ones are restored when it's popped. Its contents are kept, so clear it first
+    /// to start over.
+    #[inline]
+    pub fn push_render_target(&mut self, target: &RenderTarget) {
+        self.push_render_target_id(target.id(), target.width(), target.height());
+    }
+
+    /// Like [`Painter::push_render_target`], for a `width` x `height` target
+    /// given by its id.
+    pub fn push_render_target_id(&mut self, target: RenderTargetId, width: u32, height: u32) {
+        self.target_stack.push(TargetState {
+            target: self.target,
+            width: self.width,
+            height: self.height,
+            transform: self.transform,
+            transform_stack: std::mem::take(&mut self.transform_stack),
+            clip: self.clip,
+            clip_stack: std::mem::take(&mut self.clip_stack),
+        });
+        self.set_target(Some(target), width, height);
+        self.set_transform(Mat2x3::IDENTITY);
+        self.clip = self.issued_clip;
+    }
+
+    /// Go back to rendering into the target that was current before the last
+    /// [`Painter::push_render_target`], restoring its transform and clip.
+    pub fn pop_render_target(&mut self) {
+        let Some(state) = self.target_stack.pop() else {
+            log::warn!("pop_render_target called without a matching push_render_target");
+            return;
+        };
+        self.set_target(state.target, state.width, state.height);
+        self.transform_stack = state.transform_stack;
+        self.set_transform(state.transform);
+        self.clip = state.clip;
+        self.clip_stack = state.clip_stack;
+    }
+
+    /// Render target of the following commands, `None` for the window.
+    #[inline]
+    pub fn render_target(&self) -> Option<RenderTargetId> {
+        self.target
+    }
+
+    /// Switch to `target` and its projection, with a [`Command::Target`].
+    fn set_target(&mut self, target: Option<RenderTargetId>, width: u32, height: u32) {
+        (self.target, self.width, self.height) = (target, width, height);
+        self.proj = Mat2x3::default_proj(width as f32, height as f32);
+        self.commands.push(Command::Target {
+            target,
+            width,
+            height,
+        });
+        // switching targets resets the scissor
+        self.issued_clip = Rect::new(0.0, 0.0, width as f32, height as f32);
+    }
+
+    /// Draw with `shader` instead of the default shader until the matching
+    /// [`Painter::pop_shader`].
+    ///
+    /// The shader has to outlive the frame. The software renderer ignores shaders.
+    #[inline]
+    pub fn push_shader(&mut self, shader: &Shader) {
+        self.push_shader_id(Some(shader.id()));
+    }
+
+    /// Like [`Painter::push_shader`], for a shader given by id, or the default
+    /// shader for `None`.
+    pub fn push_shader_id(&mut self, shader: Option<ShaderId>) {
+        self.shader_stack.push(self.shader);
+        self.shader = shader;
     }
 
+    /// Go back to the shader that was current before the last [`Painter::push_shader`].
+    pub fn pop_shader(&mut self) {
+        match self.shader_stack.pop() {
+            Some(shader) => self.shader = shader,
+            None => log::warn!("pop_shader called without a matching push_shader"),
+        }
+    }
+
+    /// Shader of the following draws, `None` for the default shader.
+    #[inline]
+    pub fn shader(&self) -> Option<ShaderId> {
+        self.shader
+    }
+
+    /// Issue a [`Command::Shader`] if the shader changed since the last one.
+    ///
+    /// Called before every draw, like [`Painter::issue_clip`].
+    fn issue_shader(&mut self) {
+        if self.shader != self.issued_shader {
+            self.commands.push(Command::Shader(self.shader));
+            self.issued_shader = self.shader;
+        }
+    }
+
+    /// Issue a [`Command::Clip`] if the clip rect changed since the last one.
+    ///
+    /// Called before every clear and draw, so pushing and popping clip rects
+    /// without drawing in between doesn't record anything.
+    fn issue_clip(&mut self) {
+        if self.clip != self.issued_clip {
+            self.commands.push(Command::Clip(self.clip));
+            self.issued_clip = self.clip;
+        }
+    }
+
+    /// Append `num` default vertices and return them for filling in.
     pub fn allocate_vertices(&mut self, num: usize) -> &mut [Vertex] {
-        self.vertices.reserve(num);
         let len = self.vertices.len();
-        &mut self.vertices[len..=len + num]
+        self.vertices.resize(len + num, Vertex::default());
+        &mut self.vertices[len..]
     }
 
     #[inline]
     pub fn clear(&mut self, color: impl Into<Color>) {
+        self.issue_clip();
         self.commands.push(Command::Clear(color.into()));
     }
 
+    /// Bind a texture for the following draws, `None` draws untextured.
+    #[inline]
+    pub fn set_texture(&mut self, texture: Option<TextureId>) {
+        self.texture = texture;
+    }
+
+    /// Set how the following draws blend with the framebuffer.
+    #[inline]
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    /// Fill the following shapes with `gradient`, multiplied with their colors and
+    /// textures. `None` goes back to plain colors.
+    ///
+    /// The gradient is in local coordinates, under the transform at the time each
+    /// shape is drawn.
+    #[inline]
+    pub fn set_gradient(&mut self, gradient: Option<Gradient>) {
+        self.gradient = gradient;
+    }
+
+    /// Index into `gradients` of the current gradient under the current transform,
+    /// reusing the last entry if it's the same.
+    fn gradient_index(&mut self) -> Option<u32> {
+        let gradient = self.gradient.as_ref()?;
+        // a transform that collapses the plane draws nothing anyway
+        let inv_transform = self.transform.inverse().unwrap_or_default();
+        let is_last = self
+            .gradients
+            .last()
+            .is_some_and(|last| last.inv_transform == inv_transform && last.gradient == *gradient);
+        if !is_last {
+            self.gradients.push(GradientPaint {
+                gradient: gradient.clone(),
+                inv_transform,
+            });
+        }
+        Some(self.gradients.len() as u32 - 1)
+    }
+
+    /// Set the uniform `name` to `value` before the following draws.
+    ///
+    /// Uniforms the shader doesn't have are skipped with a warning. Draws without
+    /// uniforms leave the shader's uniforms as the previous draw set them.
+    #[inline]
+    pub fn set_uniform(&mut self, name: &str, value: impl Into<UniformValue>) {
+        self.draw_uniforms.set(name, value);
+    }
+
+    /// Stop setting uniforms before the following draws.
+    #[inline]
+    pub fn clear_uniforms(&mut self) {
+        self.draw_uniforms.clear();
+    }
+
+    /// Index into `uniforms` of the current uniforms, reusing the last entry if
+    /// it's the same.
+    fn uniforms_index(&mut self) -> Option<u32> {
+        if self.draw_uniforms.is_empty() {
+            return None;
+        }
+        if self.uniforms.last() != Some(&self.draw_uniforms) {
+            self.uniforms.push(self.draw_uniforms.clone());
+        }
+        Some(self.uniforms.len() as u32 - 1)
+    }
+
+    /// Queue a draw of `num_vertices` vertices starting at `vertex_index`.
+    ///
+    /// If the previous command draws the vertices right before these with the
+    /// same state, it's extended instead of recording a new command.
     pub fn queue_draw(&mut self, prim: PrimitiveType, vertex_index: usize, num_vertices: usize) {
+        self.issue_clip();
+        self.issue_shader();
+        self.stats.queued_draws += 1;
+        let gradient = self.gradient_index();
+        let uniforms = self.uniforms_index();
+        if let Some(Command::Draw {
+            prim: last_prim,
+            vertex_index: last_index,
+            num_vertices: last_num,
+            texture,
+            gradient: last_gradient,
+            blend_mode,
+            uniforms: last_uniforms,
+        }) = self.commands.last_mut()
+        {
+            if prim.is_list()
+                && *last_prim == prim
+                && *texture == self.texture
+                && *last_gradient == gradient
+                && *blend_mode == self.blend_mode
+                && *last_uniforms == uniforms
+                && (*last_index + *last_num) as usize == vertex_index
+            {
+                *last_num += num_vertices as u32;
+                self.stats.merged_draws += 1;
+                return;
+            }
+        }
         self.commands.push(Command::Draw {
             prim,
             vertex_index: vertex_index as u32,
             num_vertices: num_vertices as u32,
+            texture: self.texture,
+            gradient,
+            blend_mode: self.blend_mode,
+            uniforms,
         });
     }
 
-    pub fn filled_rects(&mut self, rects: &[Rect]) {
-        let mvp = self.mvp.clone();
-        let vertex_index = self.vertices.len();
-        let num_vertices = rects.len() * 6;
-        let vertices = self.allocate_vertices(num_vertices);
-        for (i, rect) in rects.iter().enumerate() {
-            let mut quad = [
+    /// Make room for `num_vertices` more vertices in the current indexed batch, and
+    /// return the index of the next vertex relative to the batch.
+    ///
+    /// If they don't fit, the pending indices are queued as a draw and a new batch
+    /// starts at the next vertex.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_vertices` is more than [`MAX_BATCH_VERTICES`].
+    pub fn reserve_indexed(&mut self, num_vertices: usize) -> u16 {
+        assert!(
+            num_vertices <= MAX_BATCH_VERTICES,
+            "{num_vertices} vertices don't fit in an indexed batch"
+        );
+        if self.vertices.len() + num_vertices - self.batch_vertex > MAX_BATCH_VERTICES {
+            self.queue_indexed_draw();
+            self.batch_vertex = self.vertices.len();
+        }
+        (self.vertices.len() - self.batch_vertex) as u16
+    }
+
+    /// Append `indices`, offset by the batch-relative index `base` returned by
+    /// [`Painter::reserve_indexed`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if an offset index doesn't fit in `u16`, before appending any.
+    #[inline]
+    pub fn push_indices(&mut self, base: u16, indices: &[u16]) {
+        let max = indices.iter().max().copied().unwrap_or(0);
+        assert!(
+            base.checked_add(max).is_some(),
+            "index {base} + {max} is past the end of the indexed batch"
+        );
+        self.indices.extend(indices.iter().map(|i| base + i));
+    }
+
+    /// Queue the indices appended since the last indexed draw as triangles.
+    ///
+    /// If the previous command is an indexed draw of the same batch with the same
+    /// state, it's extended instead of recording a new command.
+    pub fn queue_indexed_draw(&mut self) {
+        let num_indices = self.indices.len() - self.draw_index;
+        if num_indices == 0 {
+            return;
+        }
+        self.issue_clip();
+        self.issue_shader();
+        self.stats.queued_draws += 1;
+        let gradient = self.gradient_index();
+        let uniforms = self.uniforms_index();
+        if let Some(Command::DrawIndexed {
+            prim: PrimitiveType::Triangles,
+            base_vertex,
+            index_offset,
+            num_indices: last_num,
+            texture,
+            gradient: last_gradient,
+            blend_mode,
+            uniforms: last_uniforms,
+        }) = self.commands.last_mut()
+        {
+            if *base_vertex as usize == self.batch_vertex
+                && *texture == self.texture
+                && *last_gradient == gradient
+                && *blend_mode == self.blend_mode
+                && *last_uniforms == uniforms
+                && (*index_offset + *last_num) as usize == self.draw_index
+            {
+                *last_num += num_indices as u32;
+                self.stats.merged_draws += 1;
+                self.draw_index = self.indices.len();
+                return;
+            }
+        }
+        self.commands.push(Command::DrawIndexed {
+            prim: PrimitiveType::Triangles,
+            base_vertex: self.batch_vertex as u32,
+            index_offset: self.draw_index as u32,
+            num_indices: num_indices as u32,
+            texture: self.texture,
+            gradient,
+            blend_mode: self.blend_mode,
+            uniforms,
+        });
+        self.draw_index = self.indices.len();
+    }
+
+    /// Draw call counts for the current frame.
+    pub fn stats(&self) -> DrawStats {
+        let draw_calls = self
+            .commands
+            .iter()
+            .filter(|c| matches!(c, Command::Draw { .. } | Command::DrawIndexed { .. }))
+            .count();
+        DrawStats {
+            draw_calls,
+            commands: self.commands.len(),
+            ..self.stats
+        }
+    }
+
+    /// Reorder the recorded draws so draws with the same state end up next to
+    /// each other and get merged, without changing the rendered image.
+    ///
+    /// A draw only moves in front of earlier draws it doesn't overlap on screen,
+    /// and never past a clear, clip or shader switch. Indexed draws of list primitives are merged
+    /// by rewriting [`Painter::indices`]. Call this after recording a frame, the
+    /// sort is optional since it costs a pass over all indices.
+    pub fn sort_commands(&mut self) {
+        let old_commands = std::mem::take(&mut self.commands);
+        let old_indices = std::mem::take(&mut self.indices);
+        self.indices.reserve(old_indices.len());
+
+        let mut run: Vec<SortedDraw> = Vec::new();
+        let flush = |painter: &mut Self, run: &mut Vec<SortedDraw>| {
+            for draw in run.drain(..) {
+                let mut command = draw.command;
+                if let Command::DrawIndexed {
+                    index_offset,
+                    num_indices,
+                    ..
+                } = &mut command
+                {
+                    *index_offset = painter.indices.len() as u32;
+                    for range in draw.index_ranges {
+                        painter.indices.extend_from_slice(&old_indices[range]);
+                    }
+                    *num_indices = painter.indices.len() as u32 - *index_offset;
+                }
+                painter.commands.push(command);
+            }
+        };
+
+        for command in old_commands {
+            match command {
+                Command::None => (),
+                Command::Draw { .. } | Command::DrawIndexed { .. } => {
+                    let draw = SortedDraw::new(command, &self.vertices, &old_indices);
+                    let mut target = None;
+                    for (i, other) in run.iter().enumerate().rev() {
+                        if other.can_merge(&draw) {
+                            target = Some(i);
+                            break;
+                        }
+                        if other.overlaps(&draw) {
+                            break;
+                        }
+                    }
+                    match target {
+                        Some(i) => {
+                            let other = &mut run[i];
+                            let (min, max) = (other.bounds.min(), other.bounds.max());
+                            other.bounds = Rect::from_min_max(
+                                min.min(draw.bounds.min()),
+                                max.max(draw.bounds.max()),
+                            );
+                            other.index_ranges.extend(draw.index_ranges);
+                            self.stats.merged_draws += 1;
+                        }
+                        None => run.push(draw),
+                    }
+                }
+                Command::Clear(_)
+                | Command::Clip(_)
+                | Command::Target { .. }
+                | Command::Shader(_) => {
+                    flush(self, &mut run);
+                    self.commands.push(command);
+                }
+            }
+        }
+        flush(self, &mut run);
+        self.draw_index = self.indices.len();
+    }
+
+    /// Append a single vertex at local position `pos`, multiplied by `transform`.
+    #[inline]
+    pub(crate) fn push_vertex(&mut self, pos: Vec2, texcoord: Vec2, color: Color) {
+        self.vertices.push(Vertex {
+            position: self.transform.mul_vec2(pos),
+            texcoord,
+            color,
+        });
+    }
+
+    /// How many framebuffer pixels one local unit covers under `mvp`, along the
+    /// axis that is stretched the most.
+    pub fn pixels_per_unit(&self) -> f32 {
+        let half_size = vec2(self.width as f32, self.height as f32) * 0.5;
+        let x = self.mvp.mul_dir(Vec2::X) * half_size;
+        let y = self.mvp.mul_dir(Vec2::Y) * half_size;
+        x.length().max(y.length())
+    }
+
+    /// [`CURVE_TOLERANCE`] converted from pixels to local units under `mvp`.
+    #[inline]
+    pub fn curve_tolerance(&self) -> f32 {
+        CURVE_TOLERANCE / self.pixels_per_unit()
+    }
+
+    /// Number of segments needed to approximate an arc of `radius` local units
+    /// spanning `angle` radians, so that it deviates less than [`CURVE_TOLERANCE`]
+    /// pixels from the true curve on screen.
+    #[inline]
+    pub fn arc_segments(&self, radius: f32, angle: f32) -> usize {
+        arc_segments(radius, angle, self.curve_tolerance())
+    }
+
+    /// Append an indexed quad made of 2 triangles, without queueing a draw.
+    ///
+    /// `quad` and `texcoords` are ordered bottom left, bottom right, top right, top left.
+    /// The corners are multiplied by `transform`.
+    pub(crate) fn push_quad(&mut self, mut quad: [Vec2; 4], texcoords: [Vec2; 4], color: Color) {
+        self.transform.transform_vec2s(&mut quad);
+        let base = self.reserve_indexed(4);
+        for (position, texcoord) in quad.into_iter().zip(texcoords) {
+            self.vertices.push(Vertex {
+                position,
+                texcoord,
+                color,
+            });
+        }
+        self.push_indices(base, &[0, 1, 2, 0, 2, 3]);
+    }
+
+    /// Fill the rects with a single color.
+    pub fn filled_rects(&mut self, rects: &[Rect], color: impl Into<Color>) {
+        const TEXCOORD_QUAD: [Vec2; 4] = [
+            vec2(0.0, 1.0), // bottom left
+            vec2(1.0, 1.0), // bottom right
+            vec2(1.0, 0.0), // top right
+            vec2(0.0, 0.0), // top left
+        ];
+
+        let color = color.into();
+        for rect in rects {
+            let quad = [
                 vec2(rect.x, rect.y + rect.h),          // bottom left
                 vec2(rect.x + rect.w, rect.y + rect.h), // bottom right
                 vec2(rect.x + rect.w, rect.y),          // top right
                 vec2(rect.x, rect.y),                   // top left
             ];
-            mvp.transform_vec2s(&mut quad);
-
-            const TEXCOORD_QUAD: [Vec2; 4] = [
-                vec2(0.0, 1.0), // bottom left
-                vec2(1.0, 1.0), // bottom right
-                vec2(1.0, 0.0), // top right
-                vec2(0.0, 0.0), // top left
-            ];
-
-            // make a quad composed of 2 triangles
-            vertices[0 * (i + 1)].position = quad[0];
-            vertices[0 * (i + 1)].texcoord = TEXCOORD_QUAD[0];
-            vertices[1 * (i + 1)].position = quad[1];
-            vertices[1 * (i + 1)].texcoord = TEXCOORD_QUAD[1];
-            vertices[2 * (i + 1)].position = quad[2];
-            vertices[2 * (i + 1)].texcoord = TEXCOORD_QUAD[2];
-            vertices[3 * (i + 1)].position = quad[3];
-            vertices[3 * (i + 1)].texcoord = TEXCOORD_QUAD[3];
-            vertices[4 * (i + 1)].position = quad[0];
-            vertices[4 * (i + 1)].texcoord = TEXCOORD_QUAD[0];
-            vertices[5 * (i + 1)].position = quad[2];
-            vertices[5 * (i + 1)].texcoord = TEXCOORD_QUAD[2];
+            self.push_quad(quad, TEXCOORD_QUAD, color);
         }
 
-        self.queue_draw(PrimitiveType::Triangles, vertex_index, num_vertices);
+        self.queue_indexed_draw();
     }
 
     #[inline]
-    pub fn filled_rect(&mut self, rect: Rect) {
-        self.filled_rects(&[rect]);
+    pub fn filled_rect(&mut self, rect: Rect, color: impl Into<Color>) {
+        self.filled_rects(&[rect], color);
     }
 
-    pub unsafe fn gl_init(&mut self, gl: Context) {
+    /// Draw the whole texture stretched over `rect`, tinted by `color`.
+    pub fn textured_rect(&mut self, rect: Rect, texture: &Texture, color: impl Into<Color>) {
+        let prev = self.texture.replace(texture.id());
+        self.filled_rect(rect, color);
+        self.texture = prev;
+    }
+
+    /// Fill a rect with a color per corner: top left, top right, bottom right, bottom left.
+    ///
+    /// The colors are interpolated across the rect.
+    pub fn filled_rect_multicolor(&mut self, rect: Rect, colors: [Color; 4]) {
+        let vertex_index = self.vertices.len();
+        self.filled_rect(rect, Color::WHITE);
+        // filled_rects emits the corners as bottom left, bottom right, top right, top left
+        let [tl, tr, br, bl] = colors;
+        for (vertex, color) in self.vertices[vertex_index..]
+            .iter_mut()
+            .zip([bl, br, tr, tl])
+        {
+            vertex.color = color;
+        }
+    }
+
+    /// Create the GL resources used for rendering.
+    ///
+    /// # Safety
+    ///
+    /// `gl` must be the current GL context.
+    pub unsafe fn gl_init(&mut self, gl: &Context) {
         // construct vertex buffer object
-        self.vbo = Some(gl.create_buffer().unwrap());
+        let vbo = gl.create_buffer().unwrap();
+        self.vbo = Some(vbo);
+
+        // the element buffer binding is part of the vertex array state
+        let ebo = gl.create_buffer().unwrap();
+        self.ebo = Some(ebo);
+
+        // describe the vertex layout
+        let vao = gl.create_vertex_array().unwrap();
+        gl.bind_vertex_array(Some(vao));
+        gl.bind_buffer(ARRAY_BUFFER, Some(vbo));
+        gl.bind_buffer(ELEMENT_ARRAY_BUFFER, Some(ebo));
+        gl.enable_vertex_attrib_array(0);
+        gl.enable_vertex_attrib_array(1);
+        gl.enable_vertex_attrib_array(2);
+        set_vertex_layout(gl, 0);
+        gl.bind_vertex_array(None);
+        self.vao = Some(vao);
+
+        // compile the default shader
+        let program = create_program(
+            gl,
+            &[
+                (DEFAULT_VERTEX_SHADER, ShaderType::Vertex),
+                (DEFAULT_FRAGMENT_SHADER, ShaderType::Pixel),
+            ],
+        )
+        .expect("the default shader should compile");
+        self.proj_location = gl.get_uniform_location(program, "u_proj");
+        self.premultiplied_location = gl.get_uniform_location(program, "u_premultiplied");
+        self.gradient_uniforms = GradientUniforms::new(gl, program);
+        gl.use_program(Some(program));
+        gl.uniform_1_i32(gl.get_uniform_location(program, "u_texture").as_ref(), 0);
+        gl.use_program(None);
+        self.program = Some(program);
+
+        // untextured draws sample from a white texel
+        let white_texture = gl.create_texture().unwrap();
+        gl.bind_texture(TEXTURE_2D, Some(white_texture));
+        gl.tex_image_2d(
+            TEXTURE_2D,
+            0,
+            RGBA8 as i32,
+            1,
+            1,
+            0,
+            RGBA,
+            UNSIGNED_BYTE,
+            Some(&[255; 4]),
+        );
+        gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_MIN_FILTER, NEAREST as i32);
+        gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_MAG_FILTER, NEAREST as i32);
+        self.white_texture = Some(white_texture);
+
+        self.glyph_atlas.gl_init(gl);
+    }
+
+    /// Free all GL resources owned by the painter.
+    ///
+    /// # Safety
+    ///
+    /// `gl` must be the current GL context, the same one passed to [`Painter::gl_init`].
+    pub unsafe fn gl_destroy(&mut self, gl: &Context) {
+        if let Some(program) = self.program.take() {
+            gl.delete_program(program);
+        }
+        if let Some(vao) = self.vao.take() {
+            gl.delete_vertex_array(vao);
+        }
+        if let Some(vbo) = self.vbo.take() {
+            gl.delete_buffer(vbo);
+        }
+        if let Some(ebo) = self.ebo.take() {
+            gl.delete_buffer(ebo);
+        }
+        if let Some(white_texture) = self.white_texture.take() {
+            gl.delete_texture(white_texture);
+        }
+        self.glyph_atlas.gl_destroy(gl);
+        self.proj_location = None;
+        self.premultiplied_location = None;
+        self.gradient_uniforms = GradientUniforms::default();
+        self.uniform_caches.clear();
     }
 
-    pub unsafe fn setup_gl_render_state(&mut self, gl: Context) {
+    /// Set up the blend, scissor, shader and vertex array state for a frame.
+    ///
+    /// # Safety
+    ///
+    /// `gl` must be the current GL context, the same one passed to [`Painter::gl_init`].
+    pub unsafe fn setup_gl_render_state(&mut self, gl: &Context) {
         // enable alpha blending, disable face culling, disable depth testing, enable scissor
         gl.enable(BLEND);
-        gl.blend_equation(FUNC_ADD);
-        gl.blend_func_separate(SRC_ALPHA, ONE_MINUS_SRC_ALPHA, ONE, ONE_MINUS_SRC_ALPHA);
+        set_blend_mode(gl, BlendMode::Alpha);
         gl.disable(CULL_FACE);
         gl.disable(DEPTH_TEST);
         gl.disable(STENCIL_TEST);
         gl.enable(SCISSOR_TEST);
+        gl.bind_framebuffer(FRAMEBUFFER, None);
+
+        let (width, height) = (self.width as i32, self.height as i32);
+        gl.viewport(0, 0, width, height);
+        gl.scissor(0, 0, width, height);
+
+        gl.use_program(self.program);
+        gl.uniform_1_i32(self.premultiplied_location.as_ref(), 0);
+        gl.uniform_matrix_3_f32_slice(
+            self.proj_location.as_ref(),
+            false,
+            &self.proj.to_cols_array_3x3(),
+        );
 
+        gl.bind_vertex_array(self.vao);
         gl.bind_buffer(ARRAY_BUFFER, self.vbo);
-        // gl.enable_vertex_attrib_array(0);
+        gl.active_texture(TEXTURE0);
+    }
+
+    /// Upload the vertices and indices, and replay the recorded commands in order.
+    ///
+    /// The GL state is set up first with [`Painter::setup_gl_render_state`], so
+    /// programs left bound by [`Shader::set_uniform`] don't matter.
+    ///
+    /// # Safety
+    ///
+    /// `gl` must be the current GL context, the same one passed to [`Painter::gl_init`].
+    pub unsafe fn gl_render(&mut self, gl: &Context) {
+        self.setup_gl_render_state(gl);
+
+        let bytes = std::slice::from_raw_parts(
+            self.vertices.as_ptr() as *const u8,
+            std::mem::size_of_val(self.vertices.as_slice()),
+        );
+        gl.buffer_data_u8_slice(ARRAY_BUFFER, bytes, STREAM_DRAW);
+        let bytes = std::slice::from_raw_parts(
+            self.indices.as_ptr() as *const u8,
+            std::mem::size_of_val(self.indices.as_slice()),
+        );
+        gl.buffer_data_u8_slice(ELEMENT_ARRAY_BUFFER, bytes, STREAM_DRAW);
+        self.glyph_atlas.gl_upload(gl);
+
+        // height to flip scissor rects by, render targets aren't flipped
+        let mut flip_height = Some(self.height);
+        // base vertex of the attribute pointers, moved for each indexed batch
+        let mut layout_base = 0;
+        // projection of the current target
+        let mut proj = self.proj;
+        // uniform locations aren't `Copy` on the web
+        #[allow(clippy::clone_on_copy)]
+        let mut state = RenderState {
+            shader: None,
+            program: self.program,
+            proj_location: self.proj_location.clone(),
+            premultiplied_location: self.premultiplied_location.clone(),
+            texture: None,
+            gradient: None,
+            blend_mode: Some(BlendMode::Alpha),
+            uniforms: None,
+        };
+        let mut uniform_caches = std::mem::take(&mut self.uniform_caches);
+        let mut bind = |state: &mut RenderState,
+                        texture: Option<TextureId>,
+                        gradient: Option<u32>,
+                        blend_mode,
+                        uniforms: Option<u32>| {
+            let texture = texture.or(self.white_texture);
+            if state.texture != Some(texture) {
+                gl.bind_texture(TEXTURE_2D, texture);
+                state.texture = Some(texture);
+            }
+            if state.gradient != Some(gradient) {
+                // the gradient uniforms only exist in the default shader
+                if state.shader.is_none() {
+                    let paint = gradient.map(|i| &self.gradients[i as usize]);
+                    self.gradient_uniforms.set(gl, paint);
+                }
+                state.gradient = Some(gradient);
+            }
+            if state.blend_mode != Some(blend_mode) {
+                set_blend_mode(gl, blend_mode);
+                let premultiplied = blend_mode.is_premultiplied() as i32;
+                gl.uniform_1_i32(state.premultiplied_location.as_ref(), premultiplied);
+                state.blend_mode = Some(blend_mode);
+            }
+            if let (Some(i), Some(program)) = (uniforms, state.program) {
+                if state.uniforms != Some(i) {
+                    let cache = uniform_caches.entry(state.shader).or_default();
+                    cache.set_all(gl, program, &self.uniforms[i as usize]);
+                    state.uniforms = Some(i);
+                }
+            }
+        };
+        for command in &self.commands {
+            match *command {
+                Command::None => (),
+                Command::Clear(color) => {
+                    gl.clear_color(
+                        color.r as f32 / 255.0,
+                        color.g as f32 / 255.0,
+                        color.b as f32 / 255.0,
+                        color.a as f32 / 255.0,
+                    );
+                    gl.clear(COLOR_BUFFER_BIT);
+                }
+                Command::Clip(rect) => {
+                    // GL scissor boxes have a bottom-left origin
+                    let rect = rect.round();
+                    let y = match flip_height {
+                        Some(height) => height as i32 - (rect.y + rect.h) as i32,
+                        None => rect.y as i32,
+                    };
+                    gl.scissor(
+                        rect.x as i32,
+                        y,
+                        rect.w.max(0.0) as i32,
+                        rect.h.max(0.0) as i32,
+                    );
+                }
+                Command::Target {
+                    target,
+                    width,
+                    height,
+                } => {
+                    gl.bind_framebuffer(FRAMEBUFFER, target);
+                    gl.viewport(0, 0, width as i32, height as i32);
+                    gl.scissor(0, 0, width as i32, height as i32);
+                    // GL puts the first row at the bottom, render targets are flipped
+                    // so they're sampled with it at the top like uploaded textures
+                    proj = Mat2x3::default_proj(width as f32, height as f32);
+                    if target.is_some() {
+                        proj = Mat2x3::scale(vec2(1.0, -1.0)) * proj;
+                    }
+                    gl.uniform_matrix_3_f32_slice(
+                        state.proj_location.as_ref(),
+                        false,
+                        &proj.to_cols_array_3x3(),
+                    );
+                    flip_height = target.is_none().then_some(height);
+                }
+                Command::Shader(shader) => {
+                    let program = shader.map(|s| s.program()).or(self.program);
+                    gl.use_program(program);
+                    let location = |name| program.and_then(|p| gl.get_uniform_location(p, name));
+                    gl.uniform_1_i32(location("u_texture").as_ref(), 0);
+                    state.shader = shader;
+                    state.program = program;
+                    state.proj_location = location("u_proj");
+                    state.premultiplied_location = location("u_premultiplied");
+                    gl.uniform_matrix_3_f32_slice(
+                        state.proj_location.as_ref(),
+                        false,
+                        &proj.to_cols_array_3x3(),
+                    );
+                    // the program's uniforms are set again by the next draw
+                    state.gradient = None;
+                    state.blend_mode = None;
+                    state.uniforms = None;
+                }
+                Command::Draw {
+                    prim,
+                    vertex_index,
+                    num_vertices,
+                    texture,
+                    gradient,
+                    blend_mode,
+                    uniforms,
+                } => {
+                    bind(&mut state, texture, gradient, blend_mode, uniforms);
+                    if layout_base != 0 {
+                        set_vertex_layout(gl, 0);
+                        layout_base = 0;
+                    }
+                    gl.draw_arrays(prim.to_gl(), vertex_index as i32, num_vertices as i32);
+                }
+                Command::DrawIndexed {
+                    prim,
+                    base_vertex,
+                    index_offset,
+                    num_indices,
+                    texture,
+                    gradient,
+                    blend_mode,
+                    uniforms,
+                } => {
+                    bind(&mut state, texture, gradient, blend_mode, uniforms);
+                    // glDrawElementsBaseVertex isn't available on GLES 3 and WebGL 2,
+                    // so offset the attribute pointers instead
+                    if layout_base != base_vertex {
+                        set_vertex_layout(gl, base_vertex);
+                        layout_base = base_vertex;
+                    }
+                    let offset = (index_offset as usize * std::mem::size_of::<u16>()) as i32;
+                    gl.draw_elements(prim.to_gl(), num_indices as i32, UNSIGNED_SHORT, offset);
+                }
+            }
+        }
+
+        // forget the locations of shaders that weren't used this frame, they may be gone
+        uniform_caches.retain(|shader, _| {
+            shader.is_none()
+                || (self.commands.iter()).any(|c| matches!(c, Command::Shader(s) if s == shader))
+        });
+        self.uniform_caches = uniform_caches;
+
+        // leave the scissor test off so clears outside the painter aren't clipped
+        gl.disable(SCISSOR_TEST);
+        gl.bind_framebuffer(FRAMEBUFFER, None);
+        gl.bind_texture(TEXTURE_2D, None);
+        gl.bind_vertex_array(None);
+        gl.use_program(None);
     }
 
-    pub unsafe fn gl_render(gl: Context) {}
+    /// Read the pixels in `rect` back from `target`, or from the window's back
+    /// buffer for `None`, as an image with a top-left origin.
+    ///
+    /// `rect` is in framebuffer pixels, rounded and clamped to the framebuffer.
+    /// This reads what [`Painter::gl_render`] left there, so call it afterwards.
+    /// [`RenderTargetFormat::Rgba16F`] targets are read as floats, clamped to `[0, 1]`.
+    ///
+    /// # Safety
+    ///
+    /// `gl` must be the current GL context, the same one passed to [`Painter::gl_init`].
+    pub unsafe fn read_pixels(
+        &self,
+        gl: &Context,
+        target: Option<&RenderTarget>,
+        rect: Rect,
+    ) -> Image {
+        let (width, height) = match target {
+            Some(target) => (target.width(), target.height()),
+            None => (self.width, self.height),
+        };
+        let bounds = Rect::new(0.0, 0.0, width as f32, height as f32);
+        let rect = rect.round().intersect(&bounds);
+        let (x, y, w, h) = (rect.x as i32, rect.y as i32, rect.w as u32, rect.h as u32);
+
+        // GLES and WebGL only allow reading float color buffers as floats
+        let float = target.is_some_and(|t| t.format() == RenderTargetFormat::Rgba16F);
+        let (typ, bytes_per_channel) = if float {
+            (FLOAT, 4)
+        } else {
+            (UNSIGNED_BYTE, 1)
+        };
+        let mut pixels = vec![0; w as usize * h as usize * 4 * bytes_per_channel];
+        gl.bind_framebuffer(FRAMEBUFFER, target.map(RenderTarget::id));
+        gl.pixel_store_i32(PACK_ALIGNMENT, 1);
+        // the window has a bottom-left origin, render targets are flipped already
+        let gl_y = match target {
+            Some(_) => y,
+            None => height as i32 - y - h as i32,
+        };
+        gl.read_pixels(
+            x,
+            gl_y,
+            w as i32,
+            h as i32,
+            RGBA,
+            typ,
+            PixelPackData::Slice(&mut pixels),
+        );
+        gl.bind_framebuffer(FRAMEBUFFER, None);
+        if float {
+            pixels = pixels
+                .chunks_exact(4)
+                .map(|c| {
+                    let c = f32::from_ne_bytes([c[0], c[1], c[2], c[3]]);
+                    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+                })
+                .collect();
+        }
+
+        let mut image = Image::new(w, h, pixels).unwrap();
+        if target.is_none() {
+            image.flip_rows();
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        rect, vec2, Color, Command, Painter, Rect, Rot2, SoftwareRenderer, TextureOptions, Vec2,
+        MAX_BATCH_VERTICES,
+    };
+    use std::{
+        f32::consts::FRAC_PI_2,
+        panic::{catch_unwind, AssertUnwindSafe},
+    };
+
+    fn clips(painter: &Painter) -> Vec<Rect> {
+        let clips = painter.commands.iter().filter_map(|c| match c {
+            Command::Clip(rect) => Some(*rect),
+            _ => None,
+        });
+        clips.collect()
+    }
+
+    #[test]
+    fn nested_clips() {
+        let mut painter = Painter::default();
+        painter.begin(16, 16);
+        painter.push_clip(rect(2.0, 2.0, 10.0, 10.0));
+        painter.translate(vec2(4.0, 4.0));
+        painter.push_clip(rect(4.0, 0.0, 20.0, 4.0)); // (8, 4) to (24, 8) on screen
+        assert_eq!(painter.clip_rect(), rect(8.0, 4.0, 4.0, 4.0));
+        painter.filled_rect(rect(-4.0, -4.0, 16.0, 16.0), Color::WHITE);
+        painter.pop_clip();
+        painter.filled_rect(rect(-4.0, 9.0, 1.0, 1.0), Color::RED);
+        painter.pop_clip();
+        painter.filled_rect(rect(-4.0, -4.0, 1.0, 1.0), Color::RED);
+
+        // a clip that's pushed and popped without drawing isn't issued
+        painter.push_clip(rect(0.0, 0.0, 1.0, 1.0));
+        painter.pop_clip();
+        painter.filled_rect(rect(-4.0, -4.0, 1.0, 1.0), Color::RED);
+
+        assert_eq!(
+            clips(&painter),
+            [
+                rect(8.0, 4.0, 4.0, 4.0),
+                rect(2.0, 2.0, 10.0, 10.0),
+                rect(0.0, 0.0, 16.0, 16.0),
+            ]
+        );
+
+        let mut fb = SoftwareRenderer::new(16, 16);
+        fb.render(&painter);
+        assert_eq!(fb.pixel(8, 4), Color::WHITE);
+        assert_eq!(fb.pixel(11, 7), Color::WHITE);
+        assert_eq!(fb.pixel(7, 4), Color::INVISIBLE);
+        assert_eq!(fb.pixel(12, 7), Color::INVISIBLE);
+        assert_eq!(fb.pixel(0, 13), Color::INVISIBLE);
+        assert_eq!(fb.pixel(0, 0), Color::RED);
+    }
+
+    #[test]
+    fn nested_transforms() {
+        let mut painter = Painter::default();
+        painter.begin(32, 32);
+        // like a canvas, each call applies in the local coordinates of the previous ones
+        painter.translate(vec2(16.0, 16.0));
+        painter.push_transform();
+        painter.rotate(Rot2::from_angle(FRAC_PI_2));
+        painter.scale(vec2(2.0, 1.0));
+        painter.filled_rect(rect(0.0, 0.0, 2.0, 1.0), Color::WHITE);
+        painter.pop_transform();
+        painter.filled_rect(rect(0.0, 0.0, 2.0, 1.0), Color::WHITE);
+        painter.pop_transform();
+        // an unmatched pop keeps the transform
+        painter.filled_rect(rect(0.0, 0.0, 2.0, 1.0), Color::WHITE);
+        assert_eq!(painter.mvp, painter.proj.mul_transform(painter.transform));
+
+        // the corners of each rect, in framebuffer pixels
+        let corners: Vec<Vec<Vec2>> = painter
+            .vertices
+            .chunks(4)
+            .map(|quad| {
+                let mut corners: Vec<_> = quad.iter().map(|v| v.position.round()).collect();
+                corners.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+                corners
+            })
+            .collect();
+        assert_eq!(
+            corners,
+            [
+                // scaled along local x, which the rotation turned downwards
+                [
+                    vec2(15.0, 16.0),
+                    vec2(15.0, 20.0),
+                    vec2(16.0, 16.0),
+                    vec2(16.0, 20.0)
+                ],
+                [
+                    vec2(16.0, 16.0),
+                    vec2(16.0, 17.0),
+                    vec2(18.0, 16.0),
+                    vec2(18.0, 17.0)
+                ],
+                [
+                    vec2(16.0, 16.0),
+                    vec2(16.0, 17.0),
+                    vec2(18.0, 16.0),
+                    vec2(18.0, 17.0)
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn indexed_batches_split() {
+        let mut painter = Painter::default();
+        painter.begin(200, 100);
+        // 20000 quads need more vertices than u16 indices can address
+        let rects: Vec<Rect> = (0..20000)
+            .map(|i| rect((i % 200) as f32, (i / 200) as f32, 1.0, 1.0))
+            .collect();
+        painter.filled_rects(&rects, Color::WHITE);
+        assert_eq!(painter.vertices.len(), 4 * 20000);
+        assert_eq!(painter.indices.len(), 6 * 20000);
+
+        let draws: Vec<_> = painter
+            .commands
+            .iter()
+            .filter_map(|c| match *c {
+                Command::DrawIndexed {
+                    base_vertex,
+                    index_offset,
+                    num_indices,
+                    ..
+                } => Some((base_vertex, index_offset, num_indices)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(draws, [(0, 0, 6 * 16384), (65536, 6 * 16384, 6 * 3616)]);
+
+        let mut fb = SoftwareRenderer::new(200, 100);
+        fb.render(&painter);
+        assert!(fb.pixels().chunks(4).all(|p| p == [255; 4]));
+    }
+
+    #[test]
+    fn oversized_batches_panic() {
+        let mut painter = Painter::default();
+        painter.begin(8, 8);
+        // these are real asserts, so they also hold in release builds
+        assert_eq!(painter.reserve_indexed(MAX_BATCH_VERTICES), 0);
+        let reserve = catch_unwind(AssertUnwindSafe(|| {
+            painter.reserve_indexed(MAX_BATCH_VERTICES + 1)
+        }));
+        assert!(reserve.is_err());
+        let push = catch_unwind(AssertUnwindSafe(|| {
+            painter.push_indices(u16::MAX - 1, &[0, 1, 2])
+        }));
+        assert!(push.is_err());
+        assert!(painter.indices.is_empty());
+        painter.push_indices(u16::MAX - 2, &[0, 1, 2]);
+        assert_eq!(painter.indices, [u16::MAX - 2, u16::MAX - 1, u16::MAX]);
+    }
+
+    #[test]
+    fn merge_and_sort_draws() {
+        let mut fb = SoftwareRenderer::new(16, 16);
+        let red = fb
+            .create_texture(1, 1, &[255, 0, 0, 255], TextureOptions::NEAREST)
+            .unwrap();
+        let mut painter = Painter::default();
+        painter.begin(16, 16);
+
+        // adjacent draws with the same state merge as they're queued
+        painter.filled_rect(rect(0.0, 0.0, 4.0, 4.0), Color::WHITE);
+        painter.filled_rect(rect(4.0, 0.0, 4.0, 4.0), Color::WHITE);
+        painter.set_texture(Some(red));
+        painter.filled_rect(rect(8.0, 0.0, 4.0, 4.0), Color::WHITE);
+        painter.set_texture(None);
+        painter.filled_rect(rect(0.0, 8.0, 4.0, 4.0), Color::WHITE);
+        painter.set_texture(Some(red));
+        painter.filled_rect(rect(0.0, 8.0, 8.0, 8.0), Color::WHITE);
+        painter.set_texture(None);
+        painter.filled_rect(rect(2.0, 10.0, 2.0, 2.0), Color::WHITE);
+
+        let stats = painter.stats();
+        assert_eq!((stats.queued_draws, stats.merged_draws), (6, 1));
+        assert_eq!(stats.draw_calls, 5);
+        fb.render(&painter);
+        let unsorted = fb.pixels().to_vec();
+
+        // the last white rect is on top of the big red one, so it can't join the others
+        painter.sort_commands();
+        let stats = painter.stats();
+        assert_eq!((stats.queued_draws, stats.merged_draws), (6, 3));
+        assert_eq!(stats.draw_calls, 3);
+        fb.render(&painter);
+        assert_eq!(fb.pixels(), unsorted);
+        assert_eq!(fb.pixel(2, 10), Color::WHITE);
+        assert_eq!(fb.pixel(6, 10), Color::RED);
+    }
 }
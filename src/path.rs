@@ -0,0 +1,340 @@
+use crate::{
+    painter::{arc_segments, MAX_CURVE_SEGMENTS},
+    Color, Painter, Pos2, StrokeStyle, Vec2,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Segment {
+    MoveTo(Pos2),
+    LineTo(Pos2),
+    QuadTo(Pos2, Pos2),
+    CubicTo(Pos2, Pos2, Pos2),
+    /// Circular arc continuing from the current point, `sweep` radians clockwise.
+    Arc {
+        center: Pos2,
+        radius: f32,
+        start: f32,
+        sweep: f32,
+    },
+    Close,
+}
+
+/// A flattened subpath of a [`Path`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Contour {
+    pub points: Vec<Pos2>,
+    /// Whether the last point connects back to the first one.
+    pub closed: bool,
+}
+
+/// A vector path made of lines, Bézier curves and arcs.
+///
+/// Curves are kept exact until the path is drawn, so they can be flattened to
+/// the zoom level of the [`Painter`] they're drawn with.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path {
+    segments: Vec<Segment>,
+    /// Start of the current subpath.
+    start: Pos2,
+    /// Where the next segment starts, `None` for an empty path.
+    current: Option<Pos2>,
+    /// Whether a subpath is in progress, it ends with [`Path::close`].
+    in_subpath: bool,
+}
+
+impl Path {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Remove all segments, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.segments.clear();
+        self.current = None;
+        self.in_subpath = false;
+    }
+
+    /// Where the next segment starts.
+    #[inline]
+    pub fn current_pos(&self) -> Option<Pos2> {
+        self.current
+    }
+
+    /// Start a new subpath at `pos`.
+    pub fn move_to(&mut self, pos: Pos2) -> &mut Self {
+        self.segments.push(Segment::MoveTo(pos));
+        self.start = pos;
+        self.current = Some(pos);
+        self.in_subpath = true;
+        self
+    }
+
+    /// Return the start of the next segment, starting a subpath at `fallback`
+    /// if the path is empty, or at the current point after [`Path::close`].
+    fn begin_segment(&mut self, fallback: Pos2) -> Pos2 {
+        let pos = self.current.unwrap_or(fallback);
+        if !self.in_subpath {
+            self.move_to(pos);
+        }
+        pos
+    }
+
+    /// Add a straight line to `pos`. On an empty path, this starts a subpath instead.
+    pub fn line_to(&mut self, pos: Pos2) -> &mut Self {
+        if self.current.is_none() {
+            return self.move_to(pos);
+        }
+        self.begin_segment(pos);
+        self.segments.push(Segment::LineTo(pos));
+        self.current = Some(pos);
+        self
+    }
+
+    /// Add a quadratic Bézier curve to `pos`.
+    pub fn quad_to(&mut self, ctrl: Pos2, pos: Pos2) -> &mut Self {
+        self.begin_segment(ctrl);
+        self.segments.push(Segment::QuadTo(ctrl, pos));
+        self.current = Some(pos);
+        self
+    }
+
+    /// Add a cubic Bézier curve to `pos`.
+    pub fn cubic_to(&mut self, ctrl1: Pos2, ctrl2: Pos2, pos: Pos2) -> &mut Self {
+        self.begin_segment(ctrl1);
+        self.segments.push(Segment::CubicTo(ctrl1, ctrl2, pos));
+        self.current = Some(pos);
+        self
+    }
+
+    /// Round the corner at `corner` between the lines from the current point
+    /// to `corner` and from `corner` to `pos`, like `arcTo` on an HTML canvas.
+    ///
+    /// Adds a line to where the arc of `radius` touches the first line, then the
+    /// arc up to where it touches the second one. The path ends there, not at `pos`.
+    pub fn arc_to(&mut self, corner: Pos2, pos: Pos2, radius: f32) -> &mut Self {
+        let from = self.begin_segment(corner);
+        let (d0, d1) = ((from - corner).normalized(), (pos - corner).normalized());
+        let cos = d0.dot(d1);
+        let cross = d0.x * d1.y - d0.y * d1.x;
+        if radius <= 0.0 || cross.abs() < 1e-6 || !cos.is_finite() {
+            // collinear or degenerate, there's no corner to round
+            return self.line_to(corner);
+        }
+
+        // the arc touches both lines at the same distance from the corner
+        let half_angle = cos.clamp(-1.0, 1.0).acos() * 0.5;
+        let tangent = radius / half_angle.tan();
+        let center = corner + (d0 + d1).normalized() * (radius / half_angle.sin());
+        let (t0, t1) = (corner + d0 * tangent, corner + d1 * tangent);
+        let (a0, a1) = (t0 - center, t1 - center);
+        let sweep = (a0.x * a1.y - a0.y * a1.x).atan2(a0.dot(a1));
+
+        self.line_to(t0);
+        self.segments.push(Segment::Arc {
+            center,
+            radius,
+            start: a0.angle(),
+            sweep,
+        });
+        self.current = Some(t1);
+        self
+    }
+
+    /// Add a circular arc around `center` from `start` to `end` radians, with a
+    /// line from the current point to the start of the arc.
+    ///
+    /// Angles are clockwise, starting at the positive X axis.
+    pub fn arc(&mut self, center: Pos2, radius: f32, start: f32, end: f32) -> &mut Self {
+        self.line_to(center + Vec2::angled(start) * radius);
+        self.segments.push(Segment::Arc {
+            center,
+            radius,
+            start,
+            sweep: end - start,
+        });
+        self.current = Some(center + Vec2::angled(end) * radius);
+        self
+    }
+
+    /// Close the current subpath with a line back to its start.
+    ///
+    /// The next segment starts a new subpath from there.
+    pub fn close(&mut self) -> &mut Self {
+        if self.in_subpath {
+            self.segments.push(Segment::Close);
+            self.current = Some(self.start);
+            self.in_subpath = false;
+        }
+        self
+    }
+
+    /// Flatten the path into polylines that deviate less than `tolerance` from
+    /// the curves. Subpaths with less than 2 points are skipped.
+    pub fn flatten(&self, tolerance: f32) -> Vec<Contour> {
+        let mut contours = Vec::new();
+        let mut contour = Contour::default();
+        let mut flush = |contour: &mut Contour| {
+            let contour = std::mem::take(contour);
+            if contour.points.len() > 1 {
+                contours.push(contour);
+            }
+        };
+
+        let mut current = Pos2::ZERO;
+        for segment in &self.segments {
+            match *segment {
+                Segment::MoveTo(pos) => {
+                    flush(&mut contour);
+                    contour.points.push(pos);
+                    current = pos;
+                }
+                Segment::LineTo(pos) => {
+                    contour.points.push(pos);
+                    current = pos;
+                }
+                Segment::QuadTo(ctrl, pos) => {
+                    let [p0, p1, p2] = [current, ctrl, pos].map(Pos2::to_vec2);
+                    // the second derivative is 2 * dd, so n segments deviate
+                    // at most |dd| / (4 * n^2)
+                    let dd = (p0 - p1 * 2.0 + p2).length();
+                    let n = curve_segments(dd / 4.0, tolerance);
+                    for i in 1..=n {
+                        let t = i as f32 / n as f32;
+                        let mt = 1.0 - t;
+                        let p = p0 * (mt * mt) + p1 * (2.0 * mt * t) + p2 * (t * t);
+                        contour.points.push(p.to_pos2());
+                    }
+                    current = pos;
+                }
+                Segment::CubicTo(ctrl1, ctrl2, pos) => {
+                    let [p0, p1, p2, p3] = [current, ctrl1, ctrl2, pos].map(Pos2::to_vec2);
+                    // the second derivative is at most 6 * dd, so n segments deviate
+                    // at most 3 * dd / (4 * n^2)
+                    let dd = (p0 - p1 * 2.0 + p2)
+                        .length()
+                        .max((p1 - p2 * 2.0 + p3).length());
+                    let n = curve_segments(dd * 0.75, tolerance);
+                    for i in 1..=n {
+                        let t = i as f32 / n as f32;
+                        let mt = 1.0 - t;
+                        let p = p0 * (mt * mt * mt)
+                            + p1 * (3.0 * mt * mt * t)
+                            + p2 * (3.0 * mt * t * t)
+                            + p3 * (t * t * t);
+                        contour.points.push(p.to_pos2());
+                    }
+                    current = pos;
+                }
+                Segment::Arc {
+                    center,
+                    radius,
+                    start,
+                    sweep,
+                } => {
+                    let n = arc_segments(radius, sweep, tolerance);
+                    for i in 1..=n {
+                        let angle = start + sweep * i as f32 / n as f32;
+                        contour.points.push(center + Vec2::angled(angle) * radius);
+                    }
+                    current = *contour.points.last().unwrap();
+                }
+                Segment::Close => {
+                    contour.closed = true;
+                    let start = contour.points.first().copied().unwrap_or(current);
+                    flush(&mut contour);
+                    current = start;
+                }
+            }
+        }
+        flush(&mut contour);
+        contours
+    }
+}
+
+/// Number of segments for a curve whose deviation with `n` segments is at most
+/// `error / n^2`.
+#[inline]
+fn curve_segments(error: f32, tolerance: f32) -> usize {
+    let n = (error / tolerance).sqrt().ceil();
+    if n.is_finite() {
+        (n as usize).clamp(1, MAX_CURVE_SEGMENTS)
+    } else {
+        1
+    }
+}
+
+impl Painter {
+    /// Flatten `path` to the current zoom level and stroke every subpath.
+    pub fn stroke_path(&mut self, path: &Path, style: &StrokeStyle, color: impl Into<Color>) {
+        let color = color.into();
+        for contour in path.flatten(self.curve_tolerance()) {
+            self.stroke_polyline(&contour.points, contour.closed, style, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Path;
+    use crate::{pos2, vec2, Color, Painter, SoftwareRenderer, StrokeStyle};
+
+    #[test]
+    fn flatten_curves() {
+        let mut path = Path::new();
+        path.move_to(pos2(0.0, 0.0))
+            .quad_to(pos2(50.0, 100.0), pos2(100.0, 0.0))
+            .cubic_to(pos2(100.0, 50.0), pos2(0.0, 50.0), pos2(0.0, 0.0))
+            .close()
+            .line_to(pos2(-10.0, 0.0));
+        let contours = path.flatten(0.25);
+        assert_eq!(contours.len(), 2);
+        assert!(contours[0].closed && !contours[1].closed);
+        assert_eq!(contours[1].points, [pos2(0.0, 0.0), pos2(-10.0, 0.0)]);
+
+        // the quad peaks at y = 50, halfway through
+        let quad = &contours[0].points;
+        let peak = quad.iter().map(|p| p.y).fold(0.0, f32::max);
+        assert!((peak - 50.0).abs() < 0.25, "{peak}");
+        assert_eq!(*quad.last().unwrap(), pos2(0.0, 0.0));
+
+        // zooming in needs more points for the same curve
+        let mut painter = Painter::default();
+        painter.begin(256, 256);
+        let points = path.flatten(painter.curve_tolerance())[0].points.len();
+        painter.scale(vec2(8.0, 8.0));
+        assert!(path.flatten(painter.curve_tolerance())[0].points.len() > points);
+    }
+
+    #[test]
+    fn arc_to_rounds_corner() {
+        let mut path = Path::new();
+        path.move_to(pos2(0.0, 0.0))
+            .arc_to(pos2(20.0, 0.0), pos2(20.0, 20.0), 5.0)
+            .line_to(pos2(20.0, 20.0));
+        let points = &path.flatten(0.01)[0].points;
+
+        // the arc touches both edges 5 units from the corner
+        assert_eq!(points[1], pos2(15.0, 0.0));
+        let end = points[points.len() - 2];
+        assert!(end.distance(pos2(20.0, 5.0)) < 1e-4, "{end:?}");
+        let center = pos2(15.0, 5.0);
+        for p in &points[1..points.len() - 1] {
+            assert!((p.distance(center) - 5.0).abs() < 1e-4, "{p:?}");
+        }
+
+        let mut painter = Painter::default();
+        painter.begin(32, 32);
+        painter.stroke_path(&path, &StrokeStyle::new(2.0), Color::WHITE);
+        let mut fb = SoftwareRenderer::new(32, 32);
+        fb.render(&painter);
+        assert_eq!(fb.pixel(8, 0), Color::WHITE);
+        assert_eq!(fb.pixel(20, 12), Color::WHITE);
+        assert_eq!(fb.pixel(19, 0), Color::INVISIBLE);
+    }
+}
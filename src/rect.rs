@@ -1,7 +1,7 @@
 use crate::{lerp, vec2, Pos2, Vec2};
 
 #[repr(C)]
-#[derive(Clone, Copy, Default, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Rect {
     /// X position.
@@ -97,6 +97,14 @@ impl Rect {
         }
     }
 
+    /// The overlap of both rects, with a zero size if they don't overlap.
+    #[inline]
+    pub fn intersect(&self, other: &Self) -> Self {
+        let min = self.min().max(other.min());
+        let max = self.max().min(other.max()).max(min);
+        Self::from_min_max(min, max)
+    }
+
     #[inline]
     pub fn expand_to_include(&self, pos: Pos2) -> Self {
         Self {
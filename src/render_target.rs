@@ -0,0 +1,172 @@
+use crate::{Texture, TextureOptions, Vec2, XdError, XdResult};
+use glow::{Context, HasContext};
+use std::rc::Rc;
+
+/// Identifies a render target in [`Command::Target`](crate::Command::Target).
+pub type RenderTargetId = glow::Framebuffer;
+
+/// Pixel format of a [`RenderTarget`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum RenderTargetFormat {
+    /// 8 bits per channel, like uploaded textures.
+    #[default]
+    Rgba8,
+    /// 16 bit floats per channel, for colors outside of `[0, 1]` in post effects.
+    Rgba16F,
+}
+
+impl RenderTargetFormat {
+    /// The matching GL `(internal_format, format, type)` of the texture.
+    #[inline]
+    pub const fn to_gl(self) -> (u32, u32, u32) {
+        match self {
+            Self::Rgba8 => (glow::RGBA8, glow::RGBA, glow::UNSIGNED_BYTE),
+            Self::Rgba16F => (glow::RGBA16F, glow::RGBA, glow::HALF_FLOAT),
+        }
+    }
+}
+
+/// An offscreen framebuffer the [`Painter`](crate::Painter) can render into,
+/// see [`Painter::push_render_target`](crate::Painter::push_render_target).
+///
+/// The result is sampled through [`RenderTarget::texture`], with the first row
+/// at the top like uploaded textures. The GL framebuffer is freed when this is dropped.
+pub struct RenderTarget {
+    gl: Rc<Context>,
+    framebuffer: glow::Framebuffer,
+    texture: Texture,
+    format: RenderTargetFormat,
+}
+
+impl RenderTarget {
+    /// Create a render target with uninitialized contents, clear it before use.
+    pub fn new(
+        gl: &Rc<Context>,
+        width: u32,
+        height: u32,
+        format: RenderTargetFormat,
+    ) -> XdResult<Self> {
+        let texture = Texture::create(
+            gl,
+            width,
+            height,
+            format.to_gl(),
+            None,
+            TextureOptions::LINEAR,
+        )?;
+
+        let framebuffer = unsafe {
+            let framebuffer = gl.create_framebuffer().map_err(XdError::Gl)?;
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+            gl.framebuffer_texture_2d(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::TEXTURE_2D,
+                Some(texture.id()),
+                0,
+            );
+            let status = gl.check_framebuffer_status(glow::FRAMEBUFFER);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            if status != glow::FRAMEBUFFER_COMPLETE {
+                gl.delete_framebuffer(framebuffer);
+                return Err(XdError::IncompleteFramebuffer(status));
+            }
+            framebuffer
+        };
+
+        Ok(Self {
+            gl: gl.clone(),
+            framebuffer,
+            texture,
+            format,
+        })
+    }
+
+    /// Handle used to refer to this target in draw commands.
+    #[inline]
+    pub fn id(&self) -> RenderTargetId {
+        self.framebuffer
+    }
+
+    /// The texture holding the rendered pixels.
+    #[inline]
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Mutable access to the texture, to change its filtering and wrapping.
+    #[inline]
+    pub fn texture_mut(&mut self) -> &mut Texture {
+        &mut self.texture
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.texture.width()
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.texture.height()
+    }
+
+    /// Size in pixels.
+    #[inline]
+    pub fn size(&self) -> Vec2 {
+        self.texture.size()
+    }
+
+    #[inline]
+    pub const fn format(&self) -> RenderTargetFormat {
+        self.format
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        unsafe { self.gl.delete_framebuffer(self.framebuffer) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rect, vec2, Color, Command, Painter, SoftwareRenderer, TextureOptions};
+
+    #[test]
+    fn render_into_target_and_sample() {
+        let mut fb = SoftwareRenderer::new(16, 16);
+        let (target, texture) = fb.create_render_target(4, 4, TextureOptions::NEAREST);
+
+        let mut painter = Painter::default();
+        painter.begin(16, 16);
+        painter.clear(Color::BLACK);
+        painter.translate(vec2(8.0, 8.0));
+        painter.push_clip(rect(0.0, 0.0, 4.0, 4.0));
+
+        // the target starts out without the transform and clip
+        painter.push_render_target_id(target, 4, 4);
+        assert_eq!(painter.clip_rect(), rect(0.0, 0.0, 4.0, 4.0));
+        painter.clear(Color::BLUE);
+        painter.filled_rect(rect(0.0, 0.0, 2.0, 2.0), Color::RED);
+        painter.pop_render_target();
+        assert_eq!(painter.render_target(), None);
+        assert_eq!((painter.width, painter.height), (16, 16));
+
+        // stretched 2x under the restored transform, clipped to the restored clip
+        painter.set_texture(Some(texture));
+        painter.filled_rect(rect(-2.0, -2.0, 8.0, 8.0), Color::WHITE);
+        let targets = painter.commands.iter();
+        let targets = targets.filter(|c| matches!(c, Command::Target { .. }));
+        assert_eq!(targets.count(), 2);
+
+        fb.render(&painter);
+        // the first row of the target is at the top when sampled
+        assert_eq!(fb.pixel(8, 8), Color::RED);
+        assert_eq!(fb.pixel(9, 9), Color::RED);
+        assert_eq!(fb.pixel(10, 10), Color::BLUE);
+        assert_eq!(fb.pixel(11, 8), Color::BLUE);
+        assert_eq!(fb.pixel(7, 8), Color::BLACK);
+        assert_eq!(fb.pixel(12, 12), Color::BLACK);
+    }
+}
@@ -1,5 +1,14 @@
-use glow::{Context, HasContext, NativeProgram};
+use crate::{
+    uniform::UniformCache, GlslDialect, UniformValue, XdError, XdResult, DEFAULT_VERTEX_SHADER,
+};
+use glow::{Context, HasContext, Program};
+use std::{
+    fmt,
+    rc::Rc,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ShaderType {
     /// Vertex shader.
     Vertex,
@@ -7,50 +16,261 @@ pub enum ShaderType {
     Pixel,
 }
 
-pub struct Shader {
-    program: NativeProgram,
+impl ShaderType {
+    /// The matching GL shader type.
+    #[inline]
+    pub const fn to_gl(self) -> u32 {
+        match self {
+            Self::Vertex => glow::VERTEX_SHADER,
+            Self::Pixel => glow::FRAGMENT_SHADER,
+        }
+    }
+}
+
+impl fmt::Display for ShaderType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Vertex => "vertex",
+            Self::Pixel => "fragment",
+        })
+    }
 }
 
-unsafe fn create_program(gl: glow::Context, source: &str, typ: ShaderType) -> NativeProgram {
-    let program = gl.create_program().expect("couldn't create program");
+/// Identifies a shader in [`Command::Shader`](crate::Command::Shader).
+///
+/// Unlike GL program names, ids aren't reused after a shader is dropped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ShaderId {
+    program: Program,
+    serial: u64,
+}
 
-    let shader_type = match typ {
-        ShaderType::Vertex => glow::VERTEX_SHADER,
-        ShaderType::Pixel => glow::FRAGMENT_SHADER,
-    };
+impl ShaderId {
+    /// The underlying GL program.
+    #[inline]
+    pub fn program(&self) -> Program {
+        self.program
+    }
+}
 
-    let shader = gl
-        .create_shader(shader_type)
-        .expect("couldn't create shader");
-    gl.shader_source(shader, source);
+/// A linked GL program made of a vertex and a fragment shader.
+///
+/// To draw with [`Painter::push_shader`](crate::Painter::push_shader), the
+/// vertex shader takes the [`Vertex`](crate::Vertex) attributes `a_position`,
+/// `a_texcoord` and `a_color`, in framebuffer pixels for `uniform mat3 u_proj`.
+/// The painter also sets `uniform sampler2D u_texture` and `uniform bool
+/// u_premultiplied` if the program has them, see [`DEFAULT_FRAGMENT_SHADER`](crate::DEFAULT_FRAGMENT_SHADER).
+///
+/// The program is deleted when this is dropped.
+pub struct Shader {
+    gl: Rc<Context>,
+    id: ShaderId,
+    uniforms: UniformCache,
+}
+
+/// Line of the first error in a compile log, in the formats of the common drivers:
+/// `0:12(5): error` (Mesa), `0(12) : error` (NVIDIA) and `ERROR: 0:12:` (ANGLE, AMD).
+pub(crate) fn error_line(log: &str) -> Option<u32> {
+    let mut errors = log
+        .lines()
+        .filter(|line| line.to_ascii_lowercase().contains("error"));
+    errors.find_map(|line| {
+        let line = line.trim_start();
+        let line = line.strip_prefix("ERROR:").unwrap_or(line).trim_start();
+        let rest = line.trim_start_matches(|c: char| c.is_ascii_digit());
+        let rest = rest.strip_prefix(':').or_else(|| rest.strip_prefix('('))?;
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        rest[..digits].parse().ok()
+    })
+}
+
+/// Translate a single stage for the dialect of `gl` and compile it, returning
+/// the compile log as an error on failure.
+unsafe fn compile_shader(gl: &Context, source: &str, typ: ShaderType) -> XdResult<glow::Shader> {
+    let (source, offset) = GlslDialect::detect(gl).translate_with_offset(source, typ);
+    let shader = gl.create_shader(typ.to_gl()).map_err(XdError::Gl)?;
+    gl.shader_source(shader, &source);
     gl.compile_shader(shader);
     if !gl.get_shader_compile_status(shader) {
-        panic!("{}", gl.get_shader_info_log(shader));
+        let log = gl.get_shader_info_log(shader);
+        gl.delete_shader(shader);
+        return Err(XdError::ShaderCompile {
+            stage: typ,
+            // errors in the added lines have no line in the source
+            line: error_line(&log).and_then(|line| line.checked_sub(offset).filter(|&l| l > 0)),
+            log,
+        });
     }
-    gl.attach_shader(program, shader);
+    Ok(shader)
+}
 
-    gl.link_program(program);
-    if !gl.get_program_link_status(program) {
-        panic!("{}", gl.get_program_info_log(program));
+/// Compile every stage in `stages` and link them into a single program.
+pub(crate) unsafe fn create_program(
+    gl: &Context,
+    stages: &[(&str, ShaderType)],
+) -> XdResult<Program> {
+    let program = gl.create_program().map_err(XdError::Gl)?;
+    // match the `Vertex` layout for shaders without location qualifiers
+    for (index, name) in ["a_position", "a_texcoord", "a_color"]
+        .into_iter()
+        .enumerate()
+    {
+        gl.bind_attrib_location(program, index as u32, name);
     }
 
-    // the shader is now linked to the program, we can detach them from the program
+    let mut shaders = Vec::with_capacity(stages.len());
+    for &(source, typ) in stages {
+        match compile_shader(gl, source, typ) {
+            Ok(shader) => {
+                gl.attach_shader(program, shader);
+                shaders.push(shader);
+            }
+            Err(err) => {
+                for shader in shaders {
+                    gl.delete_shader(shader);
+                }
+                gl.delete_program(program);
+                return Err(err);
+            }
+        }
+    }
+
+    gl.link_program(program);
+    let linked = gl.get_program_link_status(program);
+
+    // the shaders are now linked to the program, we can detach them from the program
     // and delete them
-    gl.detach_shader(program, shader);
-    gl.delete_shader(shader);
+    for shader in shaders {
+        gl.detach_shader(program, shader);
+        gl.delete_shader(shader);
+    }
 
-    program
+    if !linked {
+        let log = gl.get_program_info_log(program);
+        gl.delete_program(program);
+        return Err(XdError::ShaderLink(log));
+    }
+    Ok(program)
 }
 
 impl Shader {
-    pub fn new(gl: Context, source: &str, typ: ShaderType) -> Self {
-        let program = unsafe { create_program(gl, source, typ) };
-        Self { program }
+    /// Compile the vertex and fragment sources and link them into a program.
+    ///
+    /// The sources are translated for the GLSL dialect of `gl` first, see [`GlslDialect`].
+    pub fn from_sources(gl: &Rc<Context>, vertex_src: &str, fragment_src: &str) -> XdResult<Self> {
+        let stages = [
+            (vertex_src, ShaderType::Vertex),
+            (fragment_src, ShaderType::Pixel),
+        ];
+        let program = unsafe { create_program(gl, &stages)? };
+        static NEXT_SERIAL: AtomicU64 = AtomicU64::new(0);
+        let serial = NEXT_SERIAL.fetch_add(1, Ordering::Relaxed);
+        Ok(Self {
+            gl: gl.clone(),
+            id: ShaderId { program, serial },
+            uniforms: UniformCache::default(),
+        })
+    }
+
+    /// Link `fragment_src` with [`DEFAULT_VERTEX_SHADER`], for effects that
+    /// only change the shading of painter draws.
+    #[inline]
+    pub fn from_fragment(gl: &Rc<Context>, fragment_src: &str) -> XdResult<Self> {
+        Self::from_sources(gl, DEFAULT_VERTEX_SHADER, fragment_src)
+    }
+
+    /// Handle used to refer to this shader in draw commands.
+    #[inline]
+    pub fn id(&self) -> ShaderId {
+        self.id
+    }
+
+    /// The underlying GL program.
+    #[inline]
+    pub fn program(&self) -> Program {
+        self.id.program
+    }
+
+    /// Set the uniform `name`, warning if the program doesn't have it.
+    ///
+    /// Locations are looked up once per name. This binds the program and leaves
+    /// it bound, WebGL has no portable way to get the previous one back. Raw GL
+    /// calls made afterwards apply to this program, while [`Painter::gl_render`](crate::Painter::gl_render)
+    /// binds the programs it needs itself.
+    pub fn set_uniform(&mut self, name: &str, value: impl Into<UniformValue>) {
+        let (value, program) = (value.into(), self.program());
+        unsafe {
+            self.gl.use_program(Some(program));
+            if let Some(location) = self.uniforms.location(&self.gl, program, name) {
+                value.set(&self.gl, location);
+            }
+        }
     }
 }
 
 impl Drop for Shader {
     fn drop(&mut self) {
-        Context::new
+        unsafe { self.gl.delete_program(self.program()) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{error_line, ShaderId};
+    use crate::{rect, Color, Command, Painter, SoftwareRenderer};
+    use std::num::NonZeroU32;
+
+    #[test]
+    fn error_lines_from_driver_logs() {
+        let logs = [
+            ("0:12(5): error: `foo' undeclared", Some(12)),
+            ("0(7) : error C0000: syntax error", Some(7)),
+            (
+                "ERROR: 0:3: 'vec5' : no matching overloaded function",
+                Some(3),
+            ),
+            ("WARNING: 0:1: unused\nERROR: 0:9: bad", Some(9)),
+            ("error: linking failed", None),
+            ("", None),
+        ];
+        for (log, line) in logs {
+            assert_eq!(error_line(log), line, "{log:?}");
+        }
+    }
+
+    #[test]
+    fn shader_switches_split_batches() {
+        let program = glow::NativeProgram(NonZeroU32::new(1).unwrap());
+        let shader = ShaderId { program, serial: 0 };
+
+        let mut painter = Painter::default();
+        painter.begin(8, 8);
+        painter.filled_rect(rect(0.0, 0.0, 1.0, 1.0), Color::RED);
+        painter.push_shader_id(Some(shader));
+        painter.filled_rect(rect(2.0, 0.0, 1.0, 1.0), Color::RED);
+        painter.filled_rect(rect(4.0, 0.0, 1.0, 1.0), Color::RED);
+        // nothing is recorded for switches without draws
+        painter.push_shader_id(None);
+        painter.pop_shader();
+        painter.pop_shader();
+        assert_eq!(painter.shader(), None);
+        painter.filled_rect(rect(6.0, 0.0, 1.0, 1.0), Color::RED);
+        painter.sort_commands();
+
+        let commands: Vec<_> = painter
+            .commands
+            .iter()
+            .map(|c| match c {
+                Command::Shader(shader) => Some(*shader),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(commands, [None, Some(Some(shader)), None, Some(None), None]);
+        assert_eq!(painter.stats().draw_calls, 3);
+
+        // the software renderer draws everything with the default pipeline
+        let mut fb = SoftwareRenderer::new(8, 8);
+        fb.render(&painter);
+        assert_eq!(fb.pixel(2, 0), Color::RED);
     }
 }
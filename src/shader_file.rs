@@ -0,0 +1,194 @@
+use crate::{Shader, ShaderId, UniformValue, Uniforms, XdResult};
+use glow::Context;
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::SystemTime,
+};
+
+/// Files polled for changes through their modification times.
+#[derive(Debug)]
+pub(crate) struct WatchedFiles {
+    paths: Vec<PathBuf>,
+    /// Modification times at the last check, `None` where unknown.
+    modified: Vec<Option<SystemTime>>,
+}
+
+impl WatchedFiles {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let modified = paths.iter().map(|path| modified(path)).collect();
+        Self { paths, modified }
+    }
+
+    /// Whether any file was modified since the last check. Missing files
+    /// don't count, so a save that replaces the file isn't seen half way.
+    pub fn changed(&mut self) -> bool {
+        let mut changed = false;
+        for (path, last) in self.paths.iter().zip(&mut self.modified) {
+            let time = modified(path);
+            if time.is_some() && time != *last {
+                *last = time;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// A [`Shader`] compiled from a vertex and a fragment file, recompiled when
+/// they change on disk.
+///
+/// Call [`ShaderFile::reload_if_changed`] once per frame, like at the start of
+/// [`App::draw`](crate::App::draw). Replaced programs are only deleted by the
+/// next call, after the draws recorded with them have been rendered.
+pub struct ShaderFile {
+    gl: Rc<Context>,
+    vertex_path: PathBuf,
+    fragment_path: PathBuf,
+    files: WatchedFiles,
+    shader: Shader,
+    /// Programs replaced since the last check, still used by recorded draws.
+    retired: Vec<Shader>,
+    /// Uniforms set again on every reloaded program.
+    uniforms: Uniforms,
+}
+
+impl ShaderFile {
+    /// Compile the shader from the files at `vertex_path` and `fragment_path`.
+    pub fn new(
+        gl: &Rc<Context>,
+        vertex_path: impl Into<PathBuf>,
+        fragment_path: impl Into<PathBuf>,
+    ) -> XdResult<Self> {
+        let (vertex_path, fragment_path) = (vertex_path.into(), fragment_path.into());
+        let files = WatchedFiles::new(vec![vertex_path.clone(), fragment_path.clone()]);
+        let shader = Self::compile(gl, &vertex_path, &fragment_path)?;
+        Ok(Self {
+            gl: gl.clone(),
+            vertex_path,
+            fragment_path,
+            files,
+            shader,
+            retired: Vec::new(),
+            uniforms: Uniforms::new(),
+        })
+    }
+
+    fn compile(gl: &Rc<Context>, vertex_path: &Path, fragment_path: &Path) -> XdResult<Shader> {
+        let vertex_src = std::fs::read_to_string(vertex_path)?;
+        let fragment_src = std::fs::read_to_string(fragment_path)?;
+        Shader::from_sources(gl, &vertex_src, &fragment_src)
+    }
+
+    /// Recompile the shader if either file changed since the last check, and
+    /// return whether the program was replaced.
+    ///
+    /// If the new sources don't compile, the error is logged and the previous
+    /// program is kept until the files change again.
+    pub fn reload_if_changed(&mut self) -> bool {
+        self.retired.clear();
+        if !self.files.changed() {
+            return false;
+        }
+        match self.reload() {
+            Ok(()) => {
+                log::info!("reloaded shader {:?}", self.fragment_path);
+                true
+            }
+            Err(err) => {
+                log::error!(
+                    "failed to reload shader {:?} + {:?}: {err}",
+                    self.vertex_path,
+                    self.fragment_path
+                );
+                false
+            }
+        }
+    }
+
+    /// Recompile the shader from the files, keeping the previous program on errors.
+    ///
+    /// The replaced program is deleted by the next [`ShaderFile::reload_if_changed`].
+    pub fn reload(&mut self) -> XdResult<()> {
+        let mut shader = Self::compile(&self.gl, &self.vertex_path, &self.fragment_path)?;
+        for (name, value) in self.uniforms.iter() {
+            shader.set_uniform(name, value);
+        }
+        let old = std::mem::replace(&mut self.shader, shader);
+        self.retired.push(old);
+        Ok(())
+    }
+
+    /// The current program.
+    #[inline]
+    pub fn shader(&self) -> &Shader {
+        &self.shader
+    }
+
+    /// Id of the current program, it changes on every reload.
+    #[inline]
+    pub fn id(&self) -> ShaderId {
+        self.shader.id()
+    }
+
+    /// Set the uniform `name` like [`Shader::set_uniform`], and again after reloads.
+    pub fn set_uniform(&mut self, name: &str, value: impl Into<UniformValue>) {
+        let value = value.into();
+        self.uniforms.set(name, value);
+        self.shader.set_uniform(name, value);
+    }
+
+    #[inline]
+    pub fn vertex_path(&self) -> &Path {
+        &self.vertex_path
+    }
+
+    #[inline]
+    pub fn fragment_path(&self) -> &Path {
+        &self.fragment_path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WatchedFiles;
+    use std::{
+        fs::File,
+        time::{Duration, SystemTime},
+    };
+
+    #[test]
+    fn watched_files_detect_changes() {
+        let dir = std::env::temp_dir().join(format!("xd2d-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b) = (dir.join("a.vert"), dir.join("b.frag"));
+        std::fs::write(&a, "a").unwrap();
+        std::fs::write(&b, "b").unwrap();
+
+        let mut files = WatchedFiles::new(vec![a.clone(), b.clone()]);
+        assert!(!files.changed());
+
+        // set the time explicitly, file systems may only store whole seconds
+        let touch = |path, secs| {
+            let time = SystemTime::now() + Duration::from_secs(secs);
+            let file = File::options().write(true).open(path).unwrap();
+            file.set_modified(time).unwrap();
+        };
+        touch(&b, 10);
+        assert!(files.changed());
+        assert!(!files.changed());
+
+        // a missing file isn't a change, its reappearance is
+        std::fs::remove_file(&a).unwrap();
+        assert!(!files.changed());
+        std::fs::write(&a, "a").unwrap();
+        touch(&a, 20);
+        assert!(files.changed());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,405 @@
+use crate::{vec2, Color, Painter, Pos2, Rect, Vec2};
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+/// Radius of each corner of a rounded rect.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct CornerRadii {
+    pub top_left: f32,
+    pub top_right: f32,
+    pub bottom_right: f32,
+    pub bottom_left: f32,
+}
+
+impl CornerRadii {
+    pub const ZERO: Self = Self::same(0.0);
+
+    /// The same radius for every corner.
+    #[inline]
+    pub const fn same(radius: f32) -> Self {
+        Self {
+            top_left: radius,
+            top_right: radius,
+            bottom_right: radius,
+            bottom_left: radius,
+        }
+    }
+
+    /// Radii clamped to `[0, min(w, h) / 2]`, so the corners of `rect` never overlap.
+    #[inline]
+    pub fn clamped(self, rect: Rect) -> Self {
+        let max = (rect.w.min(rect.h) * 0.5).max(0.0);
+        let clamp = |r: f32| r.clamp(0.0, max);
+        Self {
+            top_left: clamp(self.top_left),
+            top_right: clamp(self.top_right),
+            bottom_right: clamp(self.bottom_right),
+            bottom_left: clamp(self.bottom_left),
+        }
+    }
+
+    /// The radii in clockwise order: top left, top right, bottom right, bottom left.
+    #[inline]
+    pub const fn to_array(self) -> [f32; 4] {
+        [
+            self.top_left,
+            self.top_right,
+            self.bottom_right,
+            self.bottom_left,
+        ]
+    }
+}
+
+impl From<f32> for CornerRadii {
+    #[inline]
+    fn from(radius: f32) -> Self {
+        Self::same(radius)
+    }
+}
+
+impl From<[f32; 4]> for CornerRadii {
+    /// From top left, top right, bottom right and bottom left radii.
+    #[inline]
+    fn from([top_left, top_right, bottom_right, bottom_left]: [f32; 4]) -> Self {
+        Self {
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+        }
+    }
+}
+
+impl Painter {
+    /// Points on an ellipse around `center`, from `start` to `end` radians (inclusive).
+    ///
+    /// Angles are clockwise, starting at the positive X axis.
+    fn ellipse_points(&self, center: Pos2, radii: Vec2, start: f32, end: f32) -> Vec<Vec2> {
+        let segments = self.arc_segments(radii.x.abs().max(radii.y.abs()), end - start);
+        (0..=segments)
+            .map(|i| {
+                let angle = start + (end - start) * i as f32 / segments as f32;
+                center.to_vec2() + Vec2::angled(angle) * radii
+            })
+            .collect()
+    }
+
+    /// Fill the triangle fan between `center` and consecutive `points`.
+    ///
+    /// Texture coordinates map the bounding box of the full ellipse to `[0, 1]`.
+    fn fan(&mut self, center: Pos2, radii: Vec2, points: &[Vec2], color: Color) {
+        let min = center.to_vec2() - radii;
+        let size = radii * 2.0;
+        let uv = |p: Vec2| (p - min) / size;
+
+        let base = self.reserve_indexed(points.len() + 1);
+        self.push_vertex(center.to_vec2(), uv(center.to_vec2()), color);
+        for &p in points {
+            self.push_vertex(p, uv(p), color);
+        }
+        for i in 1..points.len() as u16 {
+            self.push_indices(base, &[0, i, i + 1]);
+        }
+        self.queue_indexed_draw();
+    }
+
+    /// Triangulate the band between `num_pairs` consecutive pairs of outer and
+    /// inner vertices, starting at the batch-relative index `base`.
+    fn band_indices(&mut self, base: u16, num_pairs: usize) {
+        for i in 1..num_pairs as u16 {
+            let (outer, inner) = (2 * i, 2 * i + 1);
+            self.push_indices(
+                base,
+                &[outer - 2, inner - 2, inner, outer - 2, inner, outer],
+            );
+        }
+    }
+
+    /// Stroke an elliptic arc `thickness` wide, centered on the outline.
+    fn stroked_arc(
+        &mut self,
+        center: Pos2,
+        radii: Vec2,
+        start: f32,
+        end: f32,
+        thickness: f32,
+        color: Color,
+    ) {
+        if thickness <= 0.0 {
+            return;
+        }
+        let radii = radii.abs();
+        let half = Vec2::splat(thickness * 0.5);
+        // the inner edge can't shrink past the center
+        let (outer, inner) = (radii + half, (radii - half).max(Vec2::ZERO));
+        let segments = self.arc_segments(outer.max_elem(), end - start);
+
+        let base = self.reserve_indexed(2 * (segments + 1));
+        for i in 0..=segments {
+            let t = i as f32 / segments as f32;
+            let dir = Vec2::angled(start + (end - start) * t);
+            self.push_vertex(center.to_vec2() + dir * outer, vec2(t, 0.0), color);
+            self.push_vertex(center.to_vec2() + dir * inner, vec2(t, 1.0), color);
+        }
+        self.band_indices(base, segments + 1);
+        self.queue_indexed_draw();
+    }
+
+    /// Clockwise outline of a rounded rect, starting where the top left corner
+    /// meets the left edge. Corner `i` gets `segments[i]` segments.
+    fn rounded_rect_points(rect: Rect, radii: [f32; 4], segments: [usize; 4]) -> Vec<Vec2> {
+        let (min, max) = (rect.min().to_vec2(), rect.max().to_vec2());
+        let [tl, tr, br, bl] = radii;
+        let corners = [
+            (vec2(min.x + tl, min.y + tl), tl, PI),
+            (vec2(max.x - tr, min.y + tr), tr, PI + FRAC_PI_2),
+            (vec2(max.x - br, max.y - br), br, 0.0),
+            (vec2(min.x + bl, max.y - bl), bl, FRAC_PI_2),
+        ];
+
+        let mut points = Vec::with_capacity(segments.iter().sum::<usize>() + 4);
+        for ((center, radius, start), segments) in corners.into_iter().zip(segments) {
+            for i in 0..=segments {
+                let angle = start + FRAC_PI_2 * i as f32 / segments as f32;
+                points.push(center + Vec2::angled(angle) * radius);
+            }
+        }
+        points
+    }
+
+    /// Fill a rect with rounded corners.
+    ///
+    /// Radii are clamped to half the smaller side of `rect`.
+    pub fn filled_rounded_rect(
+        &mut self,
+        rect: Rect,
+        radii: impl Into<CornerRadii>,
+        color: impl Into<Color>,
+    ) {
+        if rect.w <= 0.0 || rect.h <= 0.0 {
+            return;
+        }
+        let radii = radii.into().clamped(rect).to_array();
+        let segments = radii.map(|r| self.arc_segments(r, FRAC_PI_2));
+        let mut points = Self::rounded_rect_points(rect, radii, segments);
+        points.push(points[0]);
+        self.fan(rect.center(), rect.size() * 0.5, &points, color.into());
+    }
+
+    /// Stroke the outline of a rect with rounded corners, `thickness` wide and
+    /// centered on the outline.
+    ///
+    /// Radii are clamped to half the smaller side of `rect`. Corners with a zero
+    /// radius stay sharp on both sides of the stroke.
+    pub fn stroked_rounded_rect(
+        &mut self,
+        rect: Rect,
+        radii: impl Into<CornerRadii>,
+        thickness: f32,
+        color: impl Into<Color>,
+    ) {
+        if rect.w <= 0.0 || rect.h <= 0.0 || thickness <= 0.0 {
+            return;
+        }
+        let color = color.into();
+        let radii = radii.into().clamped(rect).to_array();
+        let half = thickness * 0.5;
+
+        // the inner edge can't shrink past the center of the rect
+        let outer = rect.expand(half);
+        let inner = rect.expand(-half.min(rect.w.min(rect.h) * 0.5));
+        let outer_radii = radii.map(|r| if r > 0.0 { r + half } else { 0.0 });
+        let inner_radii = radii.map(|r| (r - half).max(0.0));
+
+        // both edges need the same number of points to pair them up in a strip
+        let segments = outer_radii.map(|r| self.arc_segments(r, FRAC_PI_2));
+        let outer = Self::rounded_rect_points(outer, outer_radii, segments);
+        let inner = Self::rounded_rect_points(inner, inner_radii, segments);
+
+        // the first pair is repeated at the end, so the texcoords don't wrap around
+        let n = outer.len();
+        let base = self.reserve_indexed(2 * (n + 1));
+        for i in 0..=n {
+            let t = i as f32 / n as f32;
+            self.push_vertex(outer[i % n], vec2(t, 0.0), color);
+            self.push_vertex(inner[i % n], vec2(t, 1.0), color);
+        }
+        self.band_indices(base, n + 1);
+        self.queue_indexed_draw();
+    }
+
+    /// Fill a circle.
+    #[inline]
+    pub fn filled_circle(&mut self, center: Pos2, radius: f32, color: impl Into<Color>) {
+        self.filled_ellipse(center, Vec2::splat(radius), color);
+    }
+
+    /// Stroke the outline of a circle, `thickness` wide.
+    #[inline]
+    pub fn stroked_circle(
+        &mut self,
+        center: Pos2,
+        radius: f32,
+        thickness: f32,
+        color: impl Into<Color>,
+    ) {
+        let radii = Vec2::splat(radius);
+        self.stroked_arc(center, radii, 0.0, TAU, thickness, color.into());
+    }
+
+    /// Fill an axis-aligned ellipse with horizontal and vertical radii `radii`.
+    pub fn filled_ellipse(&mut self, center: Pos2, radii: Vec2, color: impl Into<Color>) {
+        let points = self.ellipse_points(center, radii, 0.0, TAU);
+        self.fan(center, radii, &points, color.into());
+    }
+
+    /// Stroke the outline of an axis-aligned ellipse, `thickness` wide.
+    pub fn stroked_ellipse(
+        &mut self,
+        center: Pos2,
+        radii: Vec2,
+        thickness: f32,
+        color: impl Into<Color>,
+    ) {
+        self.stroked_arc(center, radii, 0.0, TAU, thickness, color.into());
+    }
+
+    /// Stroke a circular arc `thickness` wide, from `start` to `end` radians.
+    ///
+    /// Angles are clockwise, starting at the positive X axis.
+    pub fn arc(
+        &mut self,
+        center: Pos2,
+        radius: f32,
+        start: f32,
+        end: f32,
+        thickness: f32,
+        color: impl Into<Color>,
+    ) {
+        let radii = Vec2::splat(radius);
+        self.stroked_arc(center, radii, start, end, thickness, color.into());
+    }
+
+    /// Fill a pie slice from `start` to `end` radians.
+    ///
+    /// Angles are clockwise, starting at the positive X axis.
+    pub fn pie(
+        &mut self,
+        center: Pos2,
+        radius: f32,
+        start: f32,
+        end: f32,
+        color: impl Into<Color>,
+    ) {
+        let radii = Vec2::splat(radius);
+        let points = self.ellipse_points(center, radii, start, end);
+        self.fan(center, radii, &points, color.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CornerRadii;
+    use crate::{pos2, rect, rgba, vec2, Color, Painter, SoftwareRenderer};
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    fn coverage(painter: &Painter) -> (SoftwareRenderer, usize) {
+        let mut fb = SoftwareRenderer::new(painter.width, painter.height);
+        fb.render(painter);
+        let covered = fb.pixels().chunks(4).filter(|p| p[0] == 255).count();
+        (fb, covered)
+    }
+
+    #[test]
+    fn circle_area_and_segments() {
+        let mut painter = Painter::default();
+        painter.begin(64, 64);
+        painter.filled_circle(pos2(32.0, 32.0), 20.0, Color::WHITE);
+        let (fb, covered) = coverage(&painter);
+
+        let area = PI * 20.0 * 20.0;
+        assert!((covered as f32 - area).abs() < area * 0.04, "{covered}");
+        assert_eq!(fb.pixel(32, 32), Color::WHITE);
+        assert_eq!(fb.pixel(5, 5), Color::INVISIBLE);
+
+        // zooming in needs more segments for the same local radius
+        let segments = painter.arc_segments(20.0, PI);
+        painter.scale(vec2(4.0, 4.0));
+        assert!(painter.arc_segments(20.0, PI) > segments);
+    }
+
+    #[test]
+    fn pie_and_ring() {
+        let mut painter = Painter::default();
+        painter.begin(64, 64);
+        // the bottom right quarter, angles go clockwise from +X
+        painter.pie(pos2(32.0, 32.0), 20.0, 0.0, FRAC_PI_2, Color::WHITE);
+        let (fb, _) = coverage(&painter);
+        assert_eq!(fb.pixel(40, 40), Color::WHITE);
+        assert_eq!(fb.pixel(24, 40), Color::INVISIBLE);
+        assert_eq!(fb.pixel(40, 24), Color::INVISIBLE);
+
+        painter.begin(64, 64);
+        painter.stroked_circle(pos2(32.0, 32.0), 20.0, 4.0, Color::WHITE);
+        let (fb, covered) = coverage(&painter);
+        assert_eq!(fb.pixel(32, 32), Color::INVISIBLE);
+        assert_eq!(fb.pixel(52, 32), Color::WHITE);
+        let area = PI * (22.0f32.powi(2) - 18.0f32.powi(2));
+        assert!((covered as f32 - area).abs() < area * 0.05, "{covered}");
+
+        // rings thicker than their diameter fill the circle once, empty ones draw nothing
+        painter.begin(64, 64);
+        painter.clear(Color::BLACK);
+        let half = rgba(255, 255, 255, 128);
+        painter.stroked_circle(pos2(32.0, 32.0), 8.0, 24.0, half);
+        painter.stroked_ellipse(pos2(32.0, 32.0), vec2(8.0, 20.0), -2.0, Color::WHITE);
+        painter.arc(pos2(32.0, 32.0), 8.0, 0.0, PI, 0.0, Color::WHITE);
+        let (fb, _) = coverage(&painter);
+        let expected = rgba(128, 128, 128, 255);
+        for (x, y) in [(32, 32), (31, 31), (32, 13), (51, 32)] {
+            assert_eq!(fb.pixel(x, y), expected, "({x}, {y})");
+        }
+        assert!(fb.pixels().chunks(4).all(|p| p[0] <= 128));
+        assert_eq!(fb.pixel(32, 10), Color::BLACK);
+    }
+
+    #[test]
+    fn rounded_rects() {
+        let mut painter = Painter::default();
+        painter.begin(64, 64);
+        let radii = CornerRadii {
+            top_left: 16.0,
+            top_right: 0.0,
+            bottom_right: 100.0, // clamped to half the size
+            bottom_left: 4.0,
+        };
+        painter.filled_rounded_rect(rect(8.0, 8.0, 32.0, 48.0), radii, Color::WHITE);
+        let (fb, covered) = coverage(&painter);
+
+        assert_eq!(fb.pixel(24, 32), Color::WHITE);
+        assert_eq!(fb.pixel(9, 9), Color::INVISIBLE);
+        assert_eq!(fb.pixel(39, 8), Color::WHITE);
+        assert_eq!(fb.pixel(38, 54), Color::INVISIBLE);
+        assert_eq!(fb.pixel(8, 55), Color::INVISIBLE);
+        assert_eq!(fb.pixel(9, 54), Color::WHITE);
+        let cut = |r: f32| r * r * (1.0 - PI / 4.0);
+        let area = 32.0 * 48.0 - cut(16.0) - cut(16.0) - cut(4.0);
+        assert!((covered as f32 - area).abs() < area * 0.01, "{covered}");
+
+        // a translucent stroke must not blend twice anywhere
+        painter.begin(64, 64);
+        painter.clear(Color::BLACK);
+        let half = rgba(255, 255, 255, 128);
+        let radii = [12.0, 0.0, 2.0, 32.0];
+        painter.stroked_rounded_rect(rect(8.0, 8.0, 48.0, 48.0), radii, 6.0, half);
+        let (fb, _) = coverage(&painter);
+
+        let expected = rgba(128, 128, 128, 255);
+        for (x, y) in [(32, 8), (55, 32), (6, 20), (57, 6), (55, 55)] {
+            assert_eq!(fb.pixel(x, y), expected, "({x}, {y})");
+        }
+        assert_eq!(fb.pixel(32, 32), Color::BLACK);
+        assert_eq!(fb.pixel(6, 6), Color::BLACK);
+        assert!(fb.pixels().chunks(4).all(|p| p[0] <= 128));
+    }
+}
@@ -0,0 +1,834 @@
+use crate::{
+    texture::{check_rgba8_len, region_in_bounds},
+    BlendMode, Color, Command, GradientPaint, Image, Painter, PrimitiveType, Rect, RenderTargetId,
+    TextureFilter, TextureId, TextureOptions, TextureWrap, Vec2, Vertex, XdError, XdResult,
+};
+use std::{collections::HashMap, num::NonZeroU32};
+
+/// A CPU rasterizer that replays [`Painter`] command lists into an in-memory
+/// RGBA8 framebuffer.
+///
+/// Follows the same state as the GL path: the [`BlendMode`] of each draw, a scissor rect that starts out covering
+/// the whole framebuffer every frame, and clears that respect the scissor.
+/// Useful for checking drawing code on machines without a GPU.
+///
+/// Textures referenced by draw commands have to be registered with
+/// [`SoftwareRenderer::create_texture`] first, draws with unknown textures sample white.
+/// Render targets are created with [`SoftwareRenderer::create_render_target`],
+/// draws into unknown targets are dropped.
+pub struct SoftwareRenderer {
+    width: u32,
+    height: u32,
+    /// RGBA8 pixels, top-left origin.
+    pixels: Vec<u8>,
+    /// Current scissor rect as `[x0, y0, x1, y1]` in pixels, exclusive max.
+    clip: [i32; 4],
+    textures: HashMap<TextureId, SoftwareTexture>,
+    next_texture_id: u32,
+    /// Texture of each render target.
+    render_targets: HashMap<RenderTargetId, TextureId>,
+    /// Texture of the render target being drawn into, its pixels are moved
+    /// into `pixels` until it's unbound.
+    target: Option<TextureId>,
+    /// Pixels, width and height of the framebuffer while drawing into a render target.
+    framebuffer: Option<(Vec<u8>, u32, u32)>,
+    /// Version of the painter's glyph atlas last copied into its texture.
+    glyph_atlas_version: Option<u64>,
+    /// Texture of the draw being rasterized.
+    texture: Option<TextureId>,
+    /// Gradient of the draw being rasterized.
+    gradient: Option<GradientPaint>,
+    /// Blend mode of the draw being rasterized.
+    blend_mode: BlendMode,
+    /// Whether the primitive being rasterized shrinks its texture.
+    minified: bool,
+}
+
+/// CPU copy of a texture, sampled like GL would without mipmaps.
+struct SoftwareTexture {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    options: TextureOptions,
+}
+
+/// Map a texel coordinate into `0..size` according to the wrap mode.
+#[inline]
+fn wrap_texel(i: i32, size: u32, wrap: TextureWrap) -> u32 {
+    let n = size as i32;
+    let i = match wrap {
+        TextureWrap::ClampToEdge => i.clamp(0, n - 1),
+        TextureWrap::Repeat => i.rem_euclid(n),
+        TextureWrap::MirroredRepeat => {
+            let m = i.rem_euclid(2 * n);
+            if m >= n {
+                2 * n - 1 - m
+            } else {
+                m
+            }
+        }
+    };
+    i as u32
+}
+
+impl SoftwareTexture {
+    #[inline]
+    fn texel(&self, x: i32, y: i32) -> [f32; 4] {
+        let x = wrap_texel(x, self.width, self.options.wrap_x);
+        let y = wrap_texel(y, self.height, self.options.wrap_y);
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        let p = &self.pixels[i..i + 4];
+        [p[0], p[1], p[2], p[3]].map(|c| c as f32 / 255.0)
+    }
+
+    /// Sample at normalized texture coordinates, `minified` picks the min filter.
+    fn sample(&self, uv: Vec2, minified: bool) -> [f32; 4] {
+        let filter = if minified {
+            self.options.min_filter
+        } else {
+            self.options.mag_filter
+        };
+        let (x, y) = (uv.x * self.width as f32, uv.y * self.height as f32);
+        match filter {
+            TextureFilter::Nearest => self.texel(x.floor() as i32, y.floor() as i32),
+            TextureFilter::Linear => {
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i32, y0 as i32);
+                let t00 = self.texel(x0, y0);
+                let t10 = self.texel(x0 + 1, y0);
+                let t01 = self.texel(x0, y0 + 1);
+                let t11 = self.texel(x0 + 1, y0 + 1);
+                let mut out = [0.0; 4];
+                for (c, out) in out.iter_mut().enumerate() {
+                    let top = t00[c] + (t10[c] - t00[c]) * fx;
+                    let bottom = t01[c] + (t11[c] - t01[c]) * fx;
+                    *out = top + (bottom - top) * fy;
+                }
+                out
+            }
+        }
+    }
+}
+
+/// Interpolated vertex outputs handed to the fragment stage.
+#[derive(Clone, Copy)]
+struct Varyings {
+    /// Position in framebuffer pixels, for gradients.
+    position: Vec2,
+    texcoord: Vec2,
+    color: [f32; 4],
+}
+
+impl Varyings {
+    #[inline]
+    fn from_vertex(v: &Vertex) -> Self {
+        let c = v.color;
+        Self {
+            position: v.position,
+            texcoord: v.texcoord,
+            color: [c.r, c.g, c.b, c.a].map(|c| c as f32 / 255.0),
+        }
+    }
+
+    /// Weighted sum of three varyings, for barycentric interpolation.
+    #[inline]
+    fn weighted(v: [&Self; 3], w: [f32; 3]) -> Self {
+        let mut color = [0.0; 4];
+        for (c, color) in color.iter_mut().enumerate() {
+            *color = v[0].color[c] * w[0] + v[1].color[c] * w[1] + v[2].color[c] * w[2];
+        }
+        Self {
+            position: v[0].position * w[0] + v[1].position * w[1] + v[2].position * w[2],
+            texcoord: v[0].texcoord * w[0] + v[1].texcoord * w[1] + v[2].texcoord * w[2],
+            color,
+        }
+    }
+
+    #[inline]
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self::weighted([self, other, other], [1.0 - t, t, 0.0])
+    }
+}
+
+/// Vertex positions are snapped to `1 / SUBPIXEL_STEPS` of a pixel, like GPUs do.
+const SUBPIXEL_STEPS: f32 = 256.0;
+
+/// A vertex position snapped to the subpixel grid.
+///
+/// Within a sane range of coordinates, edge functions on snapped positions are
+/// exact in `f64`, so triangles sharing an edge never both cover (or both miss)
+/// a pixel center on it.
+#[derive(Clone, Copy)]
+struct Snapped {
+    x: f64,
+    y: f64,
+}
+
+impl Snapped {
+    #[inline]
+    fn new(v: Vec2) -> Self {
+        let snap = |c: f32| ((c * SUBPIXEL_STEPS).round() / SUBPIXEL_STEPS) as f64;
+        Self {
+            x: snap(v.x),
+            y: snap(v.y),
+        }
+    }
+}
+
+/// Edge function: positive when `p` is to the right of `a -> b` on a y-down screen.
+#[inline]
+fn orient(a: Snapped, b: Snapped, p: Snapped) -> f64 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Whether `a -> b` is a top or left edge of a triangle wound clockwise on screen.
+#[inline]
+fn is_top_left(a: Snapped, b: Snapped) -> bool {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    (dy == 0.0 && dx > 0.0) || dy < 0.0
+}
+
+/// Convert a normalized float to an 8-bit channel, like GL does when writing
+/// to a normalized fixed-point framebuffer.
+#[inline]
+fn to_u8(v: f32) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[inline]
+fn premultiply(color: &mut [f32; 4]) {
+    for c in 0..3 {
+        color[c] *= color[3];
+    }
+}
+
+impl SoftwareRenderer {
+    /// Create a renderer with a transparent black framebuffer.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+            clip: [0, 0, width as i32, height as i32],
+            textures: HashMap::new(),
+            next_texture_id: 1,
+            render_targets: HashMap::new(),
+            target: None,
+            framebuffer: None,
+            glyph_atlas_version: None,
+            texture: None,
+            gradient: None,
+            blend_mode: BlendMode::Alpha,
+            minified: false,
+        }
+    }
+
+    /// Framebuffer width in pixels.
+    #[inline]
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Framebuffer height in pixels.
+    #[inline]
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// RGBA8 pixels, row by row from the top-left corner.
+    #[inline]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Color of the pixel at `(x, y)`, counting from the top-left corner.
+    #[inline]
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        let p = &self.pixels[i..i + 4];
+        Color::from_rgba(p[0], p[1], p[2], p[3])
+    }
+
+    /// Copy the pixels in `rect` out of the framebuffer, rounded and clamped to it.
+    pub fn read_pixels(&self, rect: Rect) -> Image {
+        let bounds = Rect::new(0.0, 0.0, self.width as f32, self.height as f32);
+        let rect = rect.round().intersect(&bounds);
+        let (x, y, w, h) = (
+            rect.x as usize,
+            rect.y as usize,
+            rect.w as u32,
+            rect.h as u32,
+        );
+        let row_len = w as usize * 4;
+        let mut pixels = Vec::with_capacity(row_len * h as usize);
+        for row in y..y + h as usize {
+            let start = (row * self.width as usize + x) * 4;
+            pixels.extend_from_slice(&self.pixels[start..start + row_len]);
+        }
+        Image::new(w, h, pixels).unwrap()
+    }
+
+    /// Resize the framebuffer. The contents are reset to transparent black.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        (self.width, self.height) = (width, height);
+        self.pixels = vec![0; width as usize * height as usize * 4];
+        self.clip = [0, 0, width as i32, height as i32];
+    }
+
+    /// Register an RGBA8 texture, returning the id to bind with [`Painter::set_texture`].
+    pub fn create_texture(
+        &mut self,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+        options: TextureOptions,
+    ) -> XdResult<TextureId> {
+        check_rgba8_len(width, height, pixels)?;
+        let id = glow::NativeTexture(NonZeroU32::new(self.next_texture_id).unwrap());
+        self.next_texture_id += 1;
+        self.textures.insert(
+            id,
+            SoftwareTexture {
+                width,
+                height,
+                pixels: pixels.to_vec(),
+                options,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Overwrite a region of a registered texture, see [`Texture::update`](crate::Texture::update).
+    pub fn update_texture(
+        &mut self,
+        id: TextureId,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+    ) -> XdResult<()> {
+        check_rgba8_len(width, height, pixels)?;
+        let Some(texture) = self.textures.get_mut(&id) else {
+            return Err(XdError::UnknownTexture(id));
+        };
+        if !region_in_bounds(x, y, width, height, (texture.width, texture.height)) {
+            return Err(XdError::RegionOutOfBounds {
+                x,
+                y,
+                width,
+                height,
+            });
+        }
+
+        let row_len = width as usize * 4;
+        for (row, src) in pixels.chunks_exact(row_len).enumerate() {
+            let start = ((y as usize + row) * texture.width as usize + x as usize) * 4;
+            texture.pixels[start..start + row_len].copy_from_slice(src);
+        }
+        Ok(())
+    }
+
+    /// Change the filtering and wrapping of a registered texture.
+    pub fn set_texture_options(&mut self, id: TextureId, options: TextureOptions) {
+        if let Some(texture) = self.textures.get_mut(&id) {
+            texture.options = options;
+        }
+    }
+
+    /// Forget a registered texture.
+    pub fn remove_texture(&mut self, id: TextureId) {
+        self.textures.remove(&id);
+    }
+
+    /// Register a texture for the glyph atlas of `painter`, so it can draw text.
+    ///
+    /// This is the counterpart of [`Painter::gl_init`], the atlas is copied to the
+    /// texture when rendering.
+    pub fn attach(&mut self, painter: &mut Painter) {
+        let texture = self
+            .create_texture(0, 0, &[], TextureOptions::LINEAR)
+            .unwrap();
+        painter.glyph_atlas.texture = Some(texture);
+        self.glyph_atlas_version = None;
+    }
+
+    /// Register a `width` x `height` render target, transparent black at first.
+    ///
+    /// Returns the id to pass to [`Painter::push_render_target_id`] and the id of
+    /// the texture holding its pixels.
+    pub fn create_render_target(
+        &mut self,
+        width: u32,
+        height: u32,
+        options: TextureOptions,
+    ) -> (RenderTargetId, TextureId) {
+        let pixels = vec![0; width as usize * height as usize * 4];
+        let texture = self
+            .create_texture(width, height, &pixels, options)
+            .unwrap();
+        let id = glow::NativeFramebuffer(NonZeroU32::new(self.next_texture_id).unwrap());
+        self.next_texture_id += 1;
+        self.render_targets.insert(id, texture);
+        (id, texture)
+    }
+
+    /// Forget a registered render target and its texture.
+    pub fn remove_render_target(&mut self, id: RenderTargetId) {
+        if let Some(texture) = self.render_targets.remove(&id) {
+            self.textures.remove(&texture);
+        }
+    }
+
+    /// Draw into `target`, or into the framebuffer for `None`, by moving its
+    /// pixels into `pixels`. Draws into unknown targets go to an empty framebuffer.
+    fn set_target(&mut self, target: Option<RenderTargetId>) {
+        let pixels = std::mem::take(&mut self.pixels);
+        if let Some(texture) = self.target.take().and_then(|id| self.textures.get_mut(&id)) {
+            texture.pixels = pixels;
+        } else if self.framebuffer.is_none() {
+            self.framebuffer = Some((pixels, self.width, self.height));
+        }
+
+        let (pixels, width, height) = match target {
+            None => self.framebuffer.take().unwrap(),
+            Some(target) => {
+                let id = self.render_targets.get(&target).copied();
+                match id.and_then(|id| Some((id, self.textures.get_mut(&id)?))) {
+                    Some((id, texture)) => {
+                        self.target = Some(id);
+                        let pixels = std::mem::take(&mut texture.pixels);
+                        (pixels, texture.width, texture.height)
+                    }
+                    None => {
+                        log::warn!("drawing into unknown render target {target:?}");
+                        (Vec::new(), 0, 0)
+                    }
+                }
+            }
+        };
+        (self.pixels, self.width, self.height) = (pixels, width, height);
+        self.clip = [0, 0, width as i32, height as i32];
+    }
+
+    /// Replay the commands recorded in `painter` into the framebuffer.
+    ///
+    /// The framebuffer is resized to the painter's size if it differs.
+    pub fn render(&mut self, painter: &Painter) {
+        if (self.width, self.height) != (painter.width, painter.height) {
+            self.resize(painter.width, painter.height);
+        }
+        self.clip = [0, 0, self.width as i32, self.height as i32];
+
+        let atlas = &painter.glyph_atlas;
+        if let Some(texture) = atlas.texture.and_then(|id| self.textures.get_mut(&id)) {
+            if self.glyph_atlas_version != Some(atlas.version) && !atlas.pixels.is_empty() {
+                (texture.width, texture.height) = (atlas.size, atlas.size);
+                texture.pixels.clone_from(&atlas.pixels);
+                self.glyph_atlas_version = Some(atlas.version);
+            }
+        }
+
+        for command in &painter.commands {
+            match *command {
+                Command::None => (),
+                Command::Clear(color) => self.clear(color),
+                Command::Target { target, .. } => self.set_target(target),
+                // there are no shaders, everything is drawn like the default shader does
+                Command::Shader(_) => (),
+                Command::Clip(rect) => {
+                    let rect = rect.round();
+                    let (w, h) = (self.width as i32, self.height as i32);
+                    self.clip = [
+                        (rect.x as i32).clamp(0, w),
+                        (rect.y as i32).clamp(0, h),
+                        ((rect.x + rect.w.max(0.0)) as i32).clamp(0, w),
+                        ((rect.y + rect.h.max(0.0)) as i32).clamp(0, h),
+                    ];
+                }
+                Command::Draw {
+                    prim,
+                    vertex_index,
+                    num_vertices,
+                    texture,
+                    gradient,
+                    blend_mode,
+                    // there are no shaders to set them on
+                    uniforms: _,
+                } => {
+                    let start = vertex_index as usize;
+                    let vertices = &painter.vertices[start..start + num_vertices as usize];
+                    self.gradient = gradient.map(|i| painter.gradients[i as usize].clone());
+                    self.blend_mode = blend_mode;
+                    self.draw(prim, vertices, texture);
+                }
+                Command::DrawIndexed {
+                    prim,
+                    base_vertex,
+                    index_offset,
+                    num_indices,
+                    texture,
+                    gradient,
+                    blend_mode,
+                    // there are no shaders to set them on
+                    uniforms: _,
+                } => {
+                    // expand the indices like the vertex fetch on a GPU would
+                    let start = index_offset as usize;
+                    let indices = &painter.indices[start..start + num_indices as usize];
+                    let vertices: Vec<Vertex> = indices
+                        .iter()
+                        .map(|&i| painter.vertices[base_vertex as usize + i as usize])
+                        .collect();
+                    self.gradient = gradient.map(|i| painter.gradients[i as usize].clone());
+                    self.blend_mode = blend_mode;
+                    self.draw(prim, &vertices, texture);
+                }
+            }
+        }
+        if self.framebuffer.is_some() {
+            self.set_target(None);
+        }
+    }
+
+    fn clear(&mut self, color: Color) {
+        let [x0, y0, x1, y1] = self.clip;
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let i = (y as usize * self.width as usize + x as usize) * 4;
+                self.pixels[i..i + 4].copy_from_slice(&[color.r, color.g, color.b, color.a]);
+            }
+        }
+    }
+
+    fn draw(&mut self, prim: PrimitiveType, vertices: &[Vertex], texture: Option<TextureId>) {
+        self.texture = texture;
+        self.minified = false;
+        match prim {
+            PrimitiveType::Triangles => {
+                for tri in vertices.chunks_exact(3) {
+                    self.triangle(&tri[0], &tri[1], &tri[2]);
+                }
+            }
+            PrimitiveType::TriangleStrip => {
+                for (i, tri) in vertices.windows(3).enumerate() {
+                    // every other triangle is flipped to keep the winding consistent
+                    if i % 2 == 0 {
+                        self.triangle(&tri[0], &tri[1], &tri[2]);
+                    } else {
+                        self.triangle(&tri[1], &tri[0], &tri[2]);
+                    }
+                }
+            }
+            PrimitiveType::Lines => {
+                for line in vertices.chunks_exact(2) {
+                    self.line(&line[0], &line[1]);
+                }
+            }
+            PrimitiveType::LineStrip => {
+                for line in vertices.windows(2) {
+                    self.line(&line[0], &line[1]);
+                }
+            }
+            PrimitiveType::Points => {
+                for v in vertices {
+                    let p = v.position;
+                    self.fragment(
+                        p.x.floor() as i32,
+                        p.y.floor() as i32,
+                        &Varyings::from_vertex(v),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Run the fragment stage for `v` and blend the result into pixel `(x, y)`.
+    fn fragment(&mut self, x: i32, y: i32, v: &Varyings) {
+        let [x0, y0, x1, y1] = self.clip;
+        if x < x0 || x >= x1 || y < y0 || y >= y1 {
+            return;
+        }
+
+        // the default shader multiplies the vertex color with the texture, the
+        // texture of the bound render target has no pixels and samples white
+        let mut color = v.color;
+        let texture = self.texture.and_then(|id| self.textures.get(&id));
+        if let Some(texture) = texture.filter(|t| !t.pixels.is_empty()) {
+            let texel = texture.sample(v.texcoord, self.minified);
+            for (color, texel) in color.iter_mut().zip(texel) {
+                *color *= texel;
+            }
+        }
+        // every blend mode expects premultiplied alpha
+        if !self.blend_mode.is_premultiplied() {
+            premultiply(&mut color);
+        }
+        if let Some(gradient) = &self.gradient {
+            let mut paint = gradient.color_at(v.position);
+            premultiply(&mut paint);
+            for (color, paint) in color.iter_mut().zip(paint) {
+                *color *= paint;
+            }
+        }
+        self.blend(x as u32, y as u32, color);
+    }
+
+    /// Blend the premultiplied color `src` into pixel `(x, y)` with the current blend mode.
+    fn blend(&mut self, x: u32, y: u32, src: [f32; 4]) {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        let dst = &mut self.pixels[i..i + 4];
+        let blended = self
+            .blend_mode
+            .apply(src, std::array::from_fn(|c| dst[c] as f32 / 255.0));
+        for (dst, blended) in dst.iter_mut().zip(blended) {
+            *dst = to_u8(blended);
+        }
+    }
+
+    fn triangle(&mut self, v0: &Vertex, v1: &Vertex, v2: &Vertex) {
+        // wind clockwise on screen, so interior points have positive edge functions
+        let snapped = |v: &Vertex| Snapped::new(v.position);
+        let (v1, v2) = if orient(snapped(v0), snapped(v1), snapped(v2)) < 0.0 {
+            (v2, v1)
+        } else {
+            (v1, v2)
+        };
+        let (s0, s1, s2) = (snapped(v0), snapped(v1), snapped(v2));
+        let area = orient(s0, s1, s2);
+        if area <= 0.0 || !area.is_finite() {
+            return;
+        }
+
+        let (p0, p1, p2) = (v0.position, v1.position, v2.position);
+        let [cx0, cy0, cx1, cy1] = self.clip;
+        let min_x = (p0.x.min(p1.x).min(p2.x).floor() as i32).max(cx0);
+        let min_y = (p0.y.min(p1.y).min(p2.y).floor() as i32).max(cy0);
+        let max_x = (p0.x.max(p1.x).max(p2.x).ceil() as i32).min(cx1);
+        let max_y = (p0.y.max(p1.y).max(p2.y).ceil() as i32).min(cy1);
+
+        let top_left = [
+            is_top_left(s1, s2),
+            is_top_left(s2, s0),
+            is_top_left(s0, s1),
+        ];
+        let inside = |w: f64, top_left: bool| w > 0.0 || (w == 0.0 && top_left);
+        let varyings = [v0, v1, v2].map(Varyings::from_vertex);
+
+        // the texcoords change linearly across the triangle, so the footprint of
+        // a pixel in texels decides between the min and mag filter
+        if let Some(texture) = self.texture.and_then(|id| self.textures.get(&id)) {
+            let (t0, t1, t2) = (v0.texcoord, v1.texcoord, v2.texcoord);
+            let area = area as f32;
+            let ddx = (t0 * (p1.y - p2.y) + t1 * (p2.y - p0.y) + t2 * (p0.y - p1.y)) / area;
+            let ddy = (t0 * (p2.x - p1.x) + t1 * (p0.x - p2.x) + t2 * (p1.x - p0.x)) / area;
+            let size = Vec2::new(texture.width as f32, texture.height as f32);
+            let scale = (ddx * size).length().max((ddy * size).length());
+            self.minified = scale > 1.0;
+        }
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                // sample at the pixel center
+                let p = Snapped {
+                    x: x as f64 + 0.5,
+                    y: y as f64 + 0.5,
+                };
+                let w0 = orient(s1, s2, p);
+                let w1 = orient(s2, s0, p);
+                let w2 = orient(s0, s1, p);
+                if !(inside(w0, top_left[0]) && inside(w1, top_left[1]) && inside(w2, top_left[2]))
+                {
+                    continue;
+                }
+
+                let v = Varyings::weighted(
+                    [&varyings[0], &varyings[1], &varyings[2]],
+                    [w0 / area, w1 / area, w2 / area].map(|w| w as f32),
+                );
+                self.fragment(x, y, &v);
+            }
+        }
+    }
+
+    fn line(&mut self, a: &Vertex, b: &Vertex) {
+        let d = b.position - a.position;
+        // step along the major axis, sampling the minor axis at pixel centers
+        let major = if d.x.abs() >= d.y.abs() { 0 } else { 1 };
+        let minor = 1 - major;
+        if d[major] == 0.0 {
+            return;
+        }
+
+        let (va, vb) = (Varyings::from_vertex(a), Varyings::from_vertex(b));
+        let (start, end) = (a.position[major], b.position[major]);
+        let (lo, hi) = (start.min(end), start.max(end));
+        let first = (lo - 0.5).ceil() as i32;
+        let last = (hi - 0.5).ceil() as i32;
+        for i in first..last {
+            let center = i as f32 + 0.5;
+            let t = (center - start) / d[major];
+            let pos = a.position + d * t;
+            let v = va.lerp(&vb, t);
+            let j = pos[minor].floor() as i32;
+            if major == 0 {
+                self.fragment(i, j, &v);
+            } else {
+                self.fragment(j, i, &v);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SoftwareRenderer;
+    use crate::{
+        pos2, rect, rgba, Color, Painter, PrimitiveType, TextureOptions, TextureWrap, Vertex,
+        XdError,
+    };
+
+    fn render(painter: &Painter) -> SoftwareRenderer {
+        let mut renderer = SoftwareRenderer::new(painter.width, painter.height);
+        renderer.render(painter);
+        renderer
+    }
+
+    #[test]
+    fn clear_and_rect() {
+        let mut painter = Painter::default();
+        painter.begin(8, 8);
+        painter.clear(Color::BLACK);
+        painter.filled_rect(rect(2.0, 2.0, 4.0, 3.0), Color::WHITE);
+        let fb = render(&painter);
+
+        for y in 0..8 {
+            for x in 0..8 {
+                let inside = (2..6).contains(&x) && (2..5).contains(&y);
+                let expected = if inside { Color::WHITE } else { Color::BLACK };
+                assert_eq!(fb.pixel(x, y), expected, "pixel ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn adjacent_rects_do_not_overlap() {
+        // the top-left rule must not touch the shared edge twice
+        let mut painter = Painter::default();
+        painter.begin(4, 1);
+        painter.clear(Color::INVISIBLE);
+        painter.filled_rects(
+            &[rect(0.0, 0.0, 2.0, 1.0), rect(2.0, 0.0, 2.0, 1.0)],
+            Color::WHITE,
+        );
+        let fb = render(&painter);
+        assert!(fb.pixels().chunks(4).all(|p| p == [255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn clip_limits_clear_and_draw() {
+        let mut painter = Painter::default();
+        painter.begin(4, 4);
+        painter.clear(Color::BLACK);
+        painter
+            .commands
+            .push(crate::Command::Clip(rect(1.0, 1.0, 2.0, 2.0)));
+        painter.clear(Color::RED);
+        painter.filled_rect(rect(0.0, 0.0, 4.0, 2.0), Color::WHITE);
+        let fb = render(&painter);
+
+        assert_eq!(fb.pixel(0, 0), Color::BLACK);
+        assert_eq!(fb.pixel(1, 1), Color::WHITE);
+        assert_eq!(fb.pixel(2, 2), Color::RED);
+        assert_eq!(fb.pixel(3, 3), Color::BLACK);
+    }
+
+    #[test]
+    fn lines_and_points() {
+        let mut painter = Painter::default();
+        painter.begin(4, 4);
+        painter.clear(Color::BLACK);
+        let vertex = |x, y| Vertex {
+            position: pos2(x, y).to_vec2(),
+            ..Default::default()
+        };
+        let index = painter.vertices.len();
+        painter
+            .vertices
+            .extend([vertex(0.0, 0.5), vertex(4.0, 0.5)]);
+        painter.queue_draw(PrimitiveType::Lines, index, 2);
+        painter.vertices.push(vertex(2.5, 3.5));
+        painter.queue_draw(PrimitiveType::Points, index + 2, 1);
+        let fb = render(&painter);
+
+        for x in 0..4 {
+            assert_eq!(fb.pixel(x, 0), Color::WHITE);
+            assert_eq!(fb.pixel(x, 1), Color::BLACK);
+        }
+        assert_eq!(fb.pixel(2, 3), Color::WHITE);
+        assert_eq!(fb.pixel(1, 3), Color::BLACK);
+    }
+
+    #[test]
+    fn vertex_colors_and_blending() {
+        let mut painter = Painter::default();
+        painter.begin(4, 2);
+        painter.clear(Color::BLACK);
+        painter.filled_rect(rect(0.0, 0.0, 4.0, 1.0), rgba(255, 0, 0, 128));
+        painter.filled_rect_multicolor(
+            rect(0.0, 1.0, 4.0, 1.0),
+            [Color::BLUE, Color::RED, Color::RED, Color::BLUE],
+        );
+        let fb = render(&painter);
+
+        assert_eq!(fb.pixel(0, 0), rgba(128, 0, 0, 255));
+        let (left, right) = (fb.pixel(0, 1), fb.pixel(3, 1));
+        assert!(left.b > left.r && right.r > right.b);
+    }
+
+    #[test]
+    fn textured_rect_sampling() {
+        let mut painter = Painter::default();
+        painter.begin(4, 2);
+        painter.clear(Color::BLACK);
+
+        let mut fb = SoftwareRenderer::new(4, 2);
+        #[rustfmt::skip]
+        let pixels = [
+            255, 0, 0, 255,   0, 255, 0, 255,
+            0, 0, 255, 255,   255, 255, 255, 255,
+        ];
+        let options = TextureOptions::NEAREST.with_wrap(TextureWrap::Repeat);
+        let texture = fb.create_texture(2, 2, &pixels, options).unwrap();
+
+        // the texture repeats twice horizontally
+        painter.set_texture(Some(texture));
+        let index = painter.vertices.len();
+        painter.filled_rect(rect(0.0, 0.0, 4.0, 2.0), Color::WHITE);
+        for v in &mut painter.vertices[index..] {
+            v.texcoord.x *= 2.0;
+        }
+        fb.render(&painter);
+
+        let row0 = [Color::RED, Color::LIME, Color::RED, Color::LIME];
+        let row1 = [Color::BLUE, Color::WHITE, Color::BLUE, Color::WHITE];
+        for x in 0..4 {
+            assert_eq!(fb.pixel(x, 0), row0[x as usize]);
+            assert_eq!(fb.pixel(x, 1), row1[x as usize]);
+        }
+
+        fb.update_texture(texture, 1, 1, 1, 1, &[0, 0, 0, 255])
+            .unwrap();
+        assert!(fb.update_texture(texture, 1, 1, 2, 1, &[0; 8]).is_err());
+        let overflow = fb.update_texture(texture, u32::MAX, 0, 1, 1, &[0; 4]);
+        assert!(overflow.is_err());
+        fb.render(&painter);
+        assert_eq!(fb.pixel(1, 1), Color::BLACK);
+
+        fb.remove_texture(texture);
+        let unknown = fb.update_texture(texture, 0, 0, 1, 1, &[0; 4]);
+        assert!(matches!(unknown, Err(XdError::UnknownTexture(id)) if id == texture));
+    }
+}
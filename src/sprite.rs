@@ -0,0 +1,153 @@
+use crate::{vec2, Color, Painter, Pos2, Rect, Rot2, Texture, TextureId, Vec2};
+
+/// Options for [`Painter::draw_texture_ex`].
+#[derive(Clone, Copy, PartialEq)]
+pub struct DrawTextureParams {
+    /// Region of the texture to draw, in texels. `None` draws the whole texture.
+    pub source: Option<Rect>,
+    /// Pivot point in texels, relative to the top left corner of the source rect.
+    ///
+    /// The sprite is placed so that the origin lands on the destination position,
+    /// and rotates and scales around it.
+    pub origin: Vec2,
+    /// Rotation around the origin.
+    pub rotation: Rot2,
+    /// Scale around the origin.
+    pub scale: Vec2,
+    /// Mirror the sprite horizontally.
+    pub flip_x: bool,
+    /// Mirror the sprite vertically.
+    pub flip_y: bool,
+    /// Color multiplied with the texture.
+    pub tint: Color,
+}
+
+impl Default for DrawTextureParams {
+    fn default() -> Self {
+        Self {
+            source: None,
+            origin: Vec2::ZERO,
+            rotation: Rot2::IDENTITY,
+            scale: Vec2::splat(1.0),
+            flip_x: false,
+            flip_y: false,
+            tint: Color::WHITE,
+        }
+    }
+}
+
+impl Painter {
+    /// Draw the whole texture with its top left corner at `pos`, tinted by `tint`.
+    #[inline]
+    pub fn sprite(&mut self, texture: &Texture, pos: Pos2, tint: impl Into<Color>) {
+        self.draw_texture_ex(
+            texture,
+            pos,
+            DrawTextureParams {
+                tint: tint.into(),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Draw a region of the texture at `pos` with rotation, scaling and flipping.
+    #[inline]
+    pub fn draw_texture_ex(&mut self, texture: &Texture, pos: Pos2, params: DrawTextureParams) {
+        self.draw_texture_id_ex(texture.id(), texture.size(), pos, params);
+    }
+
+    /// Same as [`Painter::draw_texture_ex`], for a texture given by id and size in texels.
+    pub fn draw_texture_id_ex(
+        &mut self,
+        texture: TextureId,
+        texture_size: Vec2,
+        pos: Pos2,
+        params: DrawTextureParams,
+    ) {
+        let source = params
+            .source
+            .unwrap_or(Rect::from_min_size(Pos2::ZERO, texture_size));
+
+        // corners relative to the origin, before rotation
+        let (min, max) = (
+            (Vec2::ZERO - params.origin) * params.scale,
+            (source.size() - params.origin) * params.scale,
+        );
+        let place = |corner: Vec2| pos.to_vec2() + params.rotation * corner;
+        let quad = [
+            place(vec2(min.x, max.y)), // bottom left
+            place(max),                // bottom right
+            place(vec2(max.x, min.y)), // top right
+            place(min),                // top left
+        ];
+
+        let (mut u0, mut u1) = (source.x / texture_size.x, source.max().x / texture_size.x);
+        let (mut v0, mut v1) = (source.y / texture_size.y, source.max().y / texture_size.y);
+        if params.flip_x {
+            std::mem::swap(&mut u0, &mut u1);
+        }
+        if params.flip_y {
+            std::mem::swap(&mut v0, &mut v1);
+        }
+        let texcoords = [vec2(u0, v1), vec2(u1, v1), vec2(u1, v0), vec2(u0, v0)];
+
+        let prev = self.texture.replace(texture);
+        self.push_quad(quad, texcoords, params.tint);
+        self.queue_indexed_draw();
+        self.texture = prev;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DrawTextureParams;
+    use crate::{pos2, rect, vec2, Color, Painter, Rot2, SoftwareRenderer, TextureOptions};
+
+    #[test]
+    fn source_rect_flip_and_rotation() {
+        let mut painter = Painter::default();
+        painter.begin(4, 4);
+        painter.clear(Color::BLACK);
+
+        let mut fb = SoftwareRenderer::new(4, 4);
+        #[rustfmt::skip]
+        let pixels = [
+            255, 0, 0, 255,   0, 255, 0, 255,   0, 0, 255, 255,
+        ];
+        let texture = fb
+            .create_texture(3, 1, &pixels, TextureOptions::NEAREST)
+            .unwrap();
+        let size = vec2(3.0, 1.0);
+
+        // green and blue texels, flipped, on the top row
+        painter.draw_texture_id_ex(
+            texture,
+            size,
+            pos2(0.0, 0.0),
+            DrawTextureParams {
+                source: Some(rect(1.0, 0.0, 2.0, 1.0)),
+                flip_x: true,
+                ..Default::default()
+            },
+        );
+        // the whole texture rotated by 90° around its first texel, down the last column
+        painter.draw_texture_id_ex(
+            texture,
+            size,
+            pos2(3.5, 1.5),
+            DrawTextureParams {
+                origin: vec2(0.5, 0.5),
+                rotation: Rot2::from_angle(std::f32::consts::FRAC_PI_2),
+                ..Default::default()
+            },
+        );
+        fb.render(&painter);
+
+        assert_eq!(fb.pixel(0, 0), Color::BLUE);
+        assert_eq!(fb.pixel(1, 0), Color::LIME);
+        assert_eq!(fb.pixel(2, 0), Color::BLACK);
+        assert_eq!(fb.pixel(3, 1), Color::RED);
+        assert_eq!(fb.pixel(3, 2), Color::LIME);
+        assert_eq!(fb.pixel(3, 3), Color::BLUE);
+    }
+}
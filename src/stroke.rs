@@ -0,0 +1,386 @@
+use crate::{vec2, Color, Painter, Pos2, Rot2, Vec2};
+
+/// How two segments of a stroked polyline are connected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum LineJoin {
+    /// Extend the outer edges until they meet, falling back to [`LineJoin::Bevel`]
+    /// past [`StrokeStyle::miter_limit`].
+    #[default]
+    Miter,
+    /// Round off the corner with a circular arc.
+    Round,
+    /// Cut the corner off with a straight edge.
+    Bevel,
+}
+
+/// How the ends of an open stroked polyline look.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum LineCap {
+    /// End exactly at the end point.
+    #[default]
+    Butt,
+    /// Add a half circle around the end point.
+    Round,
+    /// Extend past the end point by half the width.
+    Square,
+}
+
+/// Width, joins and caps of a stroke.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct StrokeStyle {
+    /// Width of the stroke in local units.
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    /// Longest allowed ratio of the miter length to the width, as in SVG.
+    /// Sharper joins are beveled.
+    pub miter_limit: f32,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl StrokeStyle {
+    /// Miter joins and butt caps with the given width.
+    #[inline]
+    pub const fn new(width: f32) -> Self {
+        Self {
+            width,
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+            miter_limit: 4.0,
+        }
+    }
+
+    #[inline]
+    pub const fn with_join(self, join: LineJoin) -> Self {
+        Self { join, ..self }
+    }
+
+    #[inline]
+    pub const fn with_cap(self, cap: LineCap) -> Self {
+        Self { cap, ..self }
+    }
+
+    #[inline]
+    pub const fn with_miter_limit(self, miter_limit: f32) -> Self {
+        Self {
+            miter_limit,
+            ..self
+        }
+    }
+}
+
+/// Left-hand normal of `dir` scaled to `len`.
+#[inline]
+fn normal(dir: Vec2, len: f32) -> Vec2 {
+    vec2(-dir.y, dir.x) * len
+}
+
+#[inline]
+fn cross(a: Vec2, b: Vec2) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+/// Corners of one segment's quad: start left, start right, end left, end right.
+#[derive(Clone, Copy)]
+struct SegmentEnds {
+    start: [Vec2; 2],
+    end: [Vec2; 2],
+}
+
+impl Painter {
+    #[inline]
+    fn stroke_triangle(&mut self, a: Vec2, b: Vec2, c: Vec2, color: Color) {
+        let base = self.reserve_indexed(3);
+        self.push_vertex(a, Vec2::ZERO, color);
+        self.push_vertex(b, Vec2::ZERO, color);
+        self.push_vertex(c, Vec2::ZERO, color);
+        self.push_indices(base, &[0, 1, 2]);
+    }
+
+    /// Fan from `pivot` along an arc around `center`, sweeping from `from` to `to`
+    /// (both offsets from `center`) through the shorter side.
+    fn stroke_fan(&mut self, pivot: Vec2, center: Vec2, from: Vec2, to: Vec2, color: Color) {
+        let angle = cross(from, to).atan2(from.dot(to));
+        self.stroke_fan_angle(pivot, center, from, angle, color);
+    }
+
+    /// Fan from `pivot` along an arc around `center`, sweeping `angle` radians from `from`.
+    fn stroke_fan_angle(
+        &mut self,
+        pivot: Vec2,
+        center: Vec2,
+        from: Vec2,
+        angle: f32,
+        color: Color,
+    ) {
+        let segments = self.arc_segments(from.length(), angle);
+        let base = self.reserve_indexed(segments + 2);
+        self.push_vertex(pivot, Vec2::ZERO, color);
+        for i in 0..=segments {
+            let next = center + Rot2::from_angle(angle * i as f32 / segments as f32) * from;
+            self.push_vertex(next, Vec2::ZERO, color);
+        }
+        for i in 1..=segments as u16 {
+            self.push_indices(base, &[0, i, i + 1]);
+        }
+    }
+
+    /// Stroke a polyline with triangles, so the result looks the same on every backend.
+    ///
+    /// With `closed` set, the last point connects back to the first one with a join
+    /// instead of caps.
+    pub fn stroke_polyline(
+        &mut self,
+        points: &[Pos2],
+        closed: bool,
+        style: &StrokeStyle,
+        color: impl Into<Color>,
+    ) {
+        let color = color.into();
+        let hw = style.width * 0.5;
+
+        // drop repeated points, they have no direction
+        let mut pts: Vec<Vec2> = Vec::with_capacity(points.len());
+        for p in points {
+            if pts
+                .last()
+                .is_none_or(|last| (*last - p.to_vec2()).length_sq() > 1e-12)
+            {
+                pts.push(p.to_vec2());
+            }
+        }
+        if closed && pts.len() > 2 && (pts[0] - pts[pts.len() - 1]).length_sq() <= 1e-12 {
+            pts.pop();
+        }
+        if pts.len() < 2 || hw <= 0.0 {
+            return;
+        }
+
+        let n = pts.len();
+        let num_segments = if closed { n } else { n - 1 };
+        let dirs: Vec<Vec2> = (0..num_segments)
+            .map(|i| (pts[(i + 1) % n] - pts[i]).normalized())
+            .collect();
+        let lens: Vec<f32> = (0..num_segments)
+            .map(|i| (pts[(i + 1) % n] - pts[i]).length())
+            .collect();
+        let mut ends: Vec<SegmentEnds> = (0..num_segments)
+            .map(|i| {
+                let (a, b, nrm) = (pts[i], pts[(i + 1) % n], normal(dirs[i], hw));
+                SegmentEnds {
+                    start: [a + nrm, a - nrm],
+                    end: [b + nrm, b - nrm],
+                }
+            })
+            .collect();
+
+        // joins, from the end of segment `a` into the start of segment `b`
+        let joins = if closed { n } else { n - 2 };
+        for j in 0..joins {
+            let (a, b) = if closed {
+                ((j + n - 1) % n, j)
+            } else {
+                (j, j + 1)
+            };
+            let p = pts[b % n];
+            let (da, db) = (dirs[a], dirs[b]);
+            let turn = cross(da, db);
+            if turn.abs() < 1e-6 && da.dot(db) > 0.0 {
+                continue; // straight continuation, the quads already meet
+            }
+
+            // the side the polyline turns towards is the inner side
+            let inner = if turn > 0.0 { 0 } else { 1 };
+            let outer = 1 - inner;
+            let sign = if inner == 0 { 1.0 } else { -1.0 };
+            let (na, nb) = (normal(da, hw), normal(db, hw));
+
+            // move both inner corners to where the inner edges intersect, so the
+            // quads don't overlap. if the segments are too short for that, the quads
+            // keep overlapping on the inner side and the join pivots around `p`
+            let bisector = (na + nb).normalized();
+            let cos_half = bisector.dot(na) / hw;
+            let mut pivot = p;
+            if cos_half > 1e-3 {
+                let miter_len = hw / cos_half;
+                let reach = (miter_len * miter_len - hw * hw).max(0.0).sqrt();
+                if reach <= lens[a].min(lens[b]) {
+                    pivot = p + bisector * (miter_len * sign);
+                    ends[a].end[inner] = pivot;
+                    ends[b].start[inner] = pivot;
+                }
+            }
+
+            let (oa, ob) = (ends[a].end[outer], ends[b].start[outer]);
+            match style.join {
+                LineJoin::Round => self.stroke_fan(pivot, p, oa - p, ob - p, color),
+                LineJoin::Bevel | LineJoin::Miter => {
+                    self.stroke_triangle(pivot, oa, ob, color);
+                    // the miter length from the inner to the outer corner is
+                    // `width / cos_half`, limited relative to the width
+                    if style.join == LineJoin::Miter
+                        && cos_half > 1e-3
+                        && 1.0 / cos_half <= style.miter_limit
+                    {
+                        let tip = p - bisector * (hw / cos_half * sign);
+                        self.stroke_triangle(oa, tip, ob, color);
+                    }
+                }
+            }
+        }
+
+        // caps
+        if !closed {
+            let last = num_segments - 1;
+            let (d0, d1) = (dirs[0], dirs[last]);
+            match style.cap {
+                LineCap::Butt => (),
+                LineCap::Square => {
+                    for corner in &mut ends[0].start {
+                        *corner -= d0 * hw;
+                    }
+                    for corner in &mut ends[last].end {
+                        *corner += d1 * hw;
+                    }
+                }
+                LineCap::Round => {
+                    let (p0, p1) = (pts[0], pts[n - 1]);
+                    let half_turn = std::f32::consts::PI;
+                    self.stroke_fan_angle(p0, p0, normal(d0, hw), half_turn, color);
+                    self.stroke_fan_angle(p1, p1, -normal(d1, hw), half_turn, color);
+                }
+            }
+        }
+
+        // segment bodies
+        for e in &ends {
+            let base = self.reserve_indexed(4);
+            for corner in e.start.into_iter().chain(e.end) {
+                self.push_vertex(corner, Vec2::ZERO, color);
+            }
+            // start left, start right, end left, end right
+            self.push_indices(base, &[0, 2, 3, 0, 3, 1]);
+        }
+
+        self.queue_indexed_draw();
+    }
+
+    /// Stroke a single line segment.
+    #[inline]
+    pub fn line(&mut self, a: Pos2, b: Pos2, style: &StrokeStyle, color: impl Into<Color>) {
+        self.stroke_polyline(&[a, b], false, style, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineCap, LineJoin, StrokeStyle};
+    use crate::{pos2, rgba, vec2, Color, Painter, SoftwareRenderer};
+
+    fn render(painter: &Painter) -> SoftwareRenderer {
+        let mut fb = SoftwareRenderer::new(painter.width, painter.height);
+        fb.render(painter);
+        fb
+    }
+
+    #[test]
+    fn caps() {
+        let mut painter = Painter::default();
+        painter.begin(16, 8);
+        let butt = StrokeStyle::new(4.0);
+        painter.line(pos2(4.0, 2.0), pos2(12.0, 2.0), &butt, Color::WHITE);
+        let square = butt.with_cap(LineCap::Square);
+        painter.line(pos2(4.0, 6.0), pos2(12.0, 6.0), &square, Color::WHITE);
+        let fb = render(&painter);
+
+        assert_eq!(fb.pixel(3, 1), Color::INVISIBLE);
+        assert_eq!(fb.pixel(4, 1), Color::WHITE);
+        assert_eq!(fb.pixel(11, 3), Color::WHITE);
+        assert_eq!(fb.pixel(12, 3), Color::INVISIBLE);
+        assert_eq!(fb.pixel(2, 5), Color::WHITE);
+        assert_eq!(fb.pixel(13, 7), Color::WHITE);
+        assert_eq!(fb.pixel(1, 5), Color::INVISIBLE);
+    }
+
+    #[test]
+    fn joins_do_not_overlap() {
+        // a translucent stroke must not blend twice where the geometry meets
+        let half = rgba(255, 255, 255, 128);
+        for join in [LineJoin::Miter, LineJoin::Round, LineJoin::Bevel] {
+            let mut painter = Painter::default();
+            painter.begin(32, 32);
+            painter.clear(Color::BLACK);
+            let style = StrokeStyle::new(6.0).with_join(join);
+            let points = [pos2(4.0, 4.0), pos2(24.0, 4.0), pos2(24.0, 28.0)];
+            painter.stroke_polyline(&points, false, &style, half);
+            let fb = render(&painter);
+
+            let expected = rgba(128, 128, 128, 255);
+            for (x, y) in [(10, 4), (24, 12), (22, 3), (23, 5), (25, 4)] {
+                assert_eq!(fb.pixel(x, y), expected, "{join:?} at ({x}, {y})");
+            }
+            assert!(fb.pixels().chunks(4).all(|p| p[0] <= 128), "{join:?}");
+
+            let corner = fb.pixel(26, 1);
+            match join {
+                LineJoin::Miter => assert_eq!(corner, expected),
+                LineJoin::Round | LineJoin::Bevel => assert_eq!(corner, Color::BLACK),
+            }
+        }
+    }
+
+    #[test]
+    fn closed_square() {
+        let mut painter = Painter::default();
+        painter.begin(32, 32);
+        let points = [
+            pos2(8.0, 8.0),
+            pos2(24.0, 8.0),
+            pos2(24.0, 24.0),
+            pos2(8.0, 24.0),
+        ];
+        painter.stroke_polyline(&points, true, &StrokeStyle::new(4.0), Color::WHITE);
+        let fb = render(&painter);
+
+        for (x, y) in [(6, 6), (25, 6), (25, 25), (6, 25), (16, 8), (8, 16)] {
+            assert_eq!(fb.pixel(x, y), Color::WHITE, "({x}, {y})");
+        }
+        assert_eq!(fb.pixel(16, 16), Color::INVISIBLE);
+        assert_eq!(fb.pixel(5, 5), Color::INVISIBLE);
+    }
+
+    #[test]
+    fn miter_limit_is_a_ratio_to_the_width() {
+        // a 20 degree join has a miter 1 / sin(10°) ≈ 5.8 times the width, past
+        // the default limit of 4, so it's beveled like in SVG
+        let p = pos2(40.0, 24.0);
+        let angle = 20f32.to_radians();
+        let b = p - vec2(angle.cos(), angle.sin()) * 30.0;
+        let points = [pos2(10.0, 24.0), p, b];
+        let tip = vec2(10f32.to_radians().cos(), 10f32.to_radians().sin());
+        for (limit, mitered) in [(4.0, false), (6.0, true)] {
+            let mut painter = Painter::default();
+            painter.begin(64, 48);
+            let style = StrokeStyle::new(4.0).with_miter_limit(limit);
+            painter.stroke_polyline(&points, false, &style, Color::WHITE);
+            let fb = render(&painter);
+
+            let near = p + tip * 7.0;
+            let color = if mitered {
+                Color::WHITE
+            } else {
+                Color::INVISIBLE
+            };
+            assert_eq!(fb.pixel(near.x as u32, near.y as u32), color, "{limit}");
+            assert_eq!(fb.pixel(39, 23), Color::WHITE, "{limit}");
+        }
+    }
+}
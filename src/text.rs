@@ -0,0 +1,506 @@
+use crate::{vec2, Color, Painter, Pos2, Rect, TextureId, Vec2, XdError, XdResult};
+use ab_glyph::{Font as _, FontArc, GlyphId, ScaleFont};
+use glow::{Context, HasContext, PixelUnpackData};
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+/// Glyphs are rasterized at this many horizontal subpixel offsets.
+const SUBPIXEL_STEPS: f32 = 4.0;
+/// Width and height of the glyph atlas before it first grows.
+const MIN_ATLAS_SIZE: u32 = 512;
+/// The glyph atlas doesn't grow past this, it's cleared instead.
+const MAX_ATLAS_SIZE: u32 = 4096;
+
+/// A TrueType or OpenType font, cheap to clone.
+///
+/// Sizes are in pixels from the lowest descender to the highest ascender, under
+/// the transform the text is drawn with.
+#[derive(Clone)]
+pub struct Font {
+    font: FontArc,
+    /// Tells fonts apart in the glyph atlas.
+    id: u32,
+}
+
+impl Font {
+    /// Parse a font from the bytes of a TTF or OTF file.
+    pub fn from_bytes(bytes: Vec<u8>) -> XdResult<Self> {
+        let font = FontArc::try_from_vec(bytes).map_err(|_| XdError::InvalidFont)?;
+        Ok(Self::new(font))
+    }
+
+    /// Parse a font from static bytes, for example from `include_bytes!`.
+    pub fn from_static(bytes: &'static [u8]) -> XdResult<Self> {
+        let font = FontArc::try_from_slice(bytes).map_err(|_| XdError::InvalidFont)?;
+        Ok(Self::new(font))
+    }
+
+    fn new(font: FontArc) -> Self {
+        static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+        Self {
+            font,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Distance from the baseline up to the highest ascender.
+    #[inline]
+    pub fn ascent(&self, size: f32) -> f32 {
+        self.font.as_scaled(size).ascent()
+    }
+
+    /// Distance from the baseline down to the lowest descender, usually negative.
+    #[inline]
+    pub fn descent(&self, size: f32) -> f32 {
+        self.font.as_scaled(size).descent()
+    }
+
+    /// Distance between the baselines of consecutive lines.
+    #[inline]
+    pub fn line_height(&self, size: f32) -> f32 {
+        let font = self.font.as_scaled(size);
+        font.height() + font.line_gap()
+    }
+
+    /// Size of `text` laid out at `size`, see [`Painter::measure_text`].
+    pub fn measure(&self, text: &str, size: f32) -> Vec2 {
+        let font = self.font.as_scaled(size);
+        let mut width: f32 = 0.0;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            let mut pen = 0.0;
+            let mut prev = None;
+            for c in line.trim_end_matches('\r').chars() {
+                let id = font.glyph_id(c);
+                if let Some(prev) = prev {
+                    pen += font.kern(prev, id);
+                }
+                pen += font.h_advance(id);
+                prev = Some(id);
+            }
+            width = width.max(pen);
+            lines += 1;
+        }
+        let height = font.height() + (lines - 1) as f32 * (font.height() + font.line_gap());
+        vec2(width, height)
+    }
+}
+
+impl std::fmt::Debug for Font {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Font").field("id", &self.id).finish()
+    }
+}
+
+/// Identifies a rasterized glyph: font, glyph, size bits and subpixel offset.
+type GlyphKey = (u32, GlyphId, u32, u8);
+
+/// Where a glyph ended up in the [`GlyphAtlas`].
+#[derive(Clone, Copy, Debug)]
+struct AtlasGlyph {
+    /// Pixel bounds relative to the pen position on the baseline.
+    bounds: Rect,
+    /// Normalized texture coordinates.
+    uv: Rect,
+}
+
+/// RGBA8 texture of rasterized glyphs, white with the coverage in alpha.
+///
+/// Glyphs are packed on shelves as they're first drawn. When the atlas runs
+/// full, glyphs that don't fit are skipped and the atlas grows at the start of
+/// the next frame, dropping all glyphs to be rasterized again.
+pub(crate) struct GlyphAtlas {
+    pub size: u32,
+    /// Empty until the first glyph is added.
+    pub pixels: Vec<u8>,
+    glyphs: HashMap<GlyphKey, Option<AtlasGlyph>>,
+    /// Next free spot on the current shelf.
+    cursor: (u32, u32),
+    shelf_height: u32,
+    overflowed: bool,
+    /// Bumped on every change to `pixels`.
+    pub version: u64,
+    /// Rows changed since the last upload to `texture`.
+    pub dirty_rows: Option<std::ops::Range<u32>>,
+    /// GL texture or software renderer texture holding the atlas.
+    pub texture: Option<TextureId>,
+    /// Size `texture` was allocated with on the GPU, 0 before the first upload.
+    uploaded_size: u32,
+}
+
+impl Default for GlyphAtlas {
+    fn default() -> Self {
+        Self {
+            size: MIN_ATLAS_SIZE,
+            pixels: Vec::new(),
+            glyphs: HashMap::new(),
+            cursor: (0, 0),
+            shelf_height: 0,
+            overflowed: false,
+            version: 0,
+            dirty_rows: None,
+            texture: None,
+            uploaded_size: 0,
+        }
+    }
+}
+
+impl GlyphAtlas {
+    /// Grow and clear the atlas if it ran full during the last frame.
+    pub fn begin_frame(&mut self) {
+        if !self.overflowed {
+            return;
+        }
+        if self.size < MAX_ATLAS_SIZE {
+            self.size *= 2;
+        } else {
+            log::warn!("glyph atlas is full at {MAX_ATLAS_SIZE}x{MAX_ATLAS_SIZE}, clearing it");
+        }
+        self.pixels.clear();
+        self.glyphs.clear();
+        (self.cursor, self.shelf_height, self.overflowed) = ((0, 0), 0, false);
+        self.dirty_rows = None;
+        self.version += 1;
+    }
+
+    /// Create the GL texture for the atlas, uploaded by [`GlyphAtlas::gl_upload`].
+    ///
+    /// # Safety
+    ///
+    /// `gl` must be the current GL context.
+    pub unsafe fn gl_init(&mut self, gl: &Context) {
+        let texture = gl.create_texture().unwrap();
+        gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+        for (param, value) in [
+            (glow::TEXTURE_MIN_FILTER, glow::LINEAR),
+            (glow::TEXTURE_MAG_FILTER, glow::LINEAR),
+            (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE),
+            (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+        ] {
+            gl.tex_parameter_i32(glow::TEXTURE_2D, param, value as i32);
+        }
+        self.texture = Some(texture);
+        self.uploaded_size = 0;
+    }
+
+    /// Free the GL texture of the atlas.
+    ///
+    /// # Safety
+    ///
+    /// `gl` must be the current GL context.
+    pub unsafe fn gl_destroy(&mut self, gl: &Context) {
+        if let Some(texture) = self.texture.take() {
+            gl.delete_texture(texture);
+        }
+    }
+
+    /// Upload the rows changed since the last upload, or the whole atlas after it grew.
+    ///
+    /// # Safety
+    ///
+    /// `gl` must be the current GL context.
+    pub unsafe fn gl_upload(&mut self, gl: &Context) {
+        let Some(texture) = self.texture.filter(|_| !self.pixels.is_empty()) else {
+            return;
+        };
+        gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+        gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+        let size = self.size as i32;
+        if self.uploaded_size != self.size {
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGBA8 as i32,
+                size,
+                size,
+                0,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                Some(&self.pixels),
+            );
+            self.uploaded_size = self.size;
+            self.dirty_rows = None;
+        } else if let Some(rows) = self.dirty_rows.take() {
+            let row_len = self.size as usize * 4;
+            gl.tex_sub_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                0,
+                rows.start as i32,
+                size,
+                rows.len() as i32,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                PixelUnpackData::Slice(
+                    &self.pixels[rows.start as usize * row_len..rows.end as usize * row_len],
+                ),
+            );
+        }
+    }
+
+    /// Find room for a `width` x `height` rect on the shelves.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        if self.cursor.0 + width > self.size {
+            self.cursor = (0, self.cursor.1 + self.shelf_height);
+            self.shelf_height = 0;
+        }
+        if self.cursor.0 + width > self.size || self.cursor.1 + height > self.size {
+            self.overflowed = true;
+            return None;
+        }
+        let pos = self.cursor;
+        self.cursor.0 += width;
+        self.shelf_height = self.shelf_height.max(height);
+        Some(pos)
+    }
+
+    /// Look up a glyph, rasterizing it on first use. `None` for glyphs without
+    /// an outline, like spaces, for glyphs larger than the largest atlas and for
+    /// glyphs that don't fit until the atlas grows.
+    fn glyph(&mut self, font: &Font, id: GlyphId, size: f32, subpixel: u8) -> Option<AtlasGlyph> {
+        let key = (font.id, id, size.to_bits(), subpixel);
+        if let Some(glyph) = self.glyphs.get(&key) {
+            return *glyph;
+        }
+
+        let offset = subpixel as f32 / SUBPIXEL_STEPS;
+        let glyph = id.with_scale_and_position(size, ab_glyph::point(offset, 0.0));
+        let Some(outline) = font.font.outline_glyph(glyph) else {
+            self.glyphs.insert(key, None);
+            return None;
+        };
+        let px_bounds = outline.px_bounds();
+        let (width, height) = (px_bounds.width() as u32, px_bounds.height() as u32);
+        // a glyph that doesn't even fit the largest atlas would clear it every frame
+        if width.max(height) + 2 > MAX_ATLAS_SIZE {
+            log::warn!("glyph of {width}x{height} pixels is too large for the glyph atlas");
+            self.glyphs.insert(key, None);
+            return None;
+        }
+        // a pixel of padding keeps linear filtering from bleeding in neighbors
+        let (x, y) = self.allocate(width + 2, height + 2)?;
+        let (x, y) = (x + 1, y + 1);
+
+        if self.pixels.is_empty() {
+            self.pixels = vec![0; self.size as usize * self.size as usize * 4];
+        }
+        outline.draw(|gx, gy, coverage| {
+            let i = (((y + gy) * self.size + x + gx) * 4) as usize;
+            self.pixels[i..i + 4].copy_from_slice(&[
+                255,
+                255,
+                255,
+                (coverage.min(1.0) * 255.0).round() as u8,
+            ]);
+        });
+        self.version += 1;
+        self.dirty_rows = Some(match self.dirty_rows.take() {
+            Some(rows) => rows.start.min(y)..rows.end.max(y + height),
+            None => y..y + height,
+        });
+
+        let size = self.size as f32;
+        let glyph = AtlasGlyph {
+            bounds: Rect::new(
+                px_bounds.min.x,
+                px_bounds.min.y,
+                width as f32,
+                height as f32,
+            ),
+            uv: Rect::new(
+                x as f32 / size,
+                y as f32 / size,
+                width as f32 / size,
+                height as f32 / size,
+            ),
+        };
+        self.glyphs.insert(key, Some(glyph));
+        Some(glyph)
+    }
+}
+
+impl Painter {
+    /// Use `font` for the following text. The font stays set across frames.
+    #[inline]
+    pub fn set_font(&mut self, font: Option<Font>) {
+        self.font = font;
+    }
+
+    #[inline]
+    pub fn font(&self) -> Option<&Font> {
+        self.font.as_ref()
+    }
+
+    /// Draw `text` with the current font, `size` pixels tall under the current transform.
+    ///
+    /// `pos` is the top left corner of the first line, which has its baseline
+    /// [`Font::ascent`] below it. Lines are separated by `\n` and spaced by
+    /// [`Font::line_height`], glyphs are kerned.
+    ///
+    /// Glyphs are rasterized into the glyph atlas at their size on screen, so
+    /// text stays sharp under scaling.
+    /// Needs [`Painter::gl_init`] or [`SoftwareRenderer::attach`](crate::SoftwareRenderer::attach)
+    /// for the atlas texture.
+    pub fn text(&mut self, pos: Pos2, text: &str, size: f32, color: impl Into<Color>) {
+        let Some(font) = self.font.clone() else {
+            log::warn!("drawing text without a font");
+            return;
+        };
+        let Some(atlas_texture) = self.glyph_atlas.texture else {
+            log::warn!("drawing text without a glyph atlas texture");
+            return;
+        };
+        let color = color.into();
+
+        // lay out in framebuffer pixels, relative to the local origin
+        let scale = self.pixels_per_unit();
+        let px_size = size * scale;
+        // nothing is visible before `begin` or under a transform that collapses the plane
+        if !(scale > 0.0 && scale.is_finite() && px_size > 0.0 && px_size.is_finite()) {
+            return;
+        }
+        let scaled = font.font.as_scaled(px_size);
+        let line_height = scaled.height() + scaled.line_gap();
+
+        let prev_texture = self.texture.replace(atlas_texture);
+        for (i, line) in text.split('\n').enumerate() {
+            let baseline = (pos.y * scale + scaled.ascent() + i as f32 * line_height).round();
+            let mut pen = pos.x * scale;
+            let mut prev = None;
+            for c in line.trim_end_matches('\r').chars() {
+                let id = scaled.glyph_id(c);
+                if let Some(prev) = prev {
+                    pen += scaled.kern(prev, id);
+                }
+                let x = pen.floor();
+                let subpixel = ((pen - x) * SUBPIXEL_STEPS) as u8;
+                if let Some(glyph) = self.glyph_atlas.glyph(&font, id, px_size, subpixel) {
+                    let (b, uv) = (glyph.bounds, glyph.uv);
+                    let (x0, y0) = ((x + b.x) / scale, (baseline + b.y) / scale);
+                    let (x1, y1) = (x0 + b.w / scale, y0 + b.h / scale);
+                    self.push_quad(
+                        [vec2(x0, y1), vec2(x1, y1), vec2(x1, y0), vec2(x0, y0)],
+                        [
+                            vec2(uv.x, uv.y + uv.h),
+                            vec2(uv.x + uv.w, uv.y + uv.h),
+                            vec2(uv.x + uv.w, uv.y),
+                            vec2(uv.x, uv.y),
+                        ],
+                        color,
+                    );
+                }
+                pen += scaled.h_advance(id);
+                prev = Some(id);
+            }
+        }
+        self.queue_indexed_draw();
+        self.texture = prev_texture;
+    }
+
+    /// Bounds of `text` drawn at `pos` with [`Painter::text`], from the ascent of
+    /// the first line to the descent of the last one.
+    ///
+    /// Zero sized at `pos` without a font.
+    pub fn measure_text(&self, pos: Pos2, text: &str, size: f32) -> Rect {
+        let size = match &self.font {
+            Some(font) => font.measure(text, size),
+            None => Vec2::ZERO,
+        };
+        Rect::new(pos.x, pos.y, size.x, size.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Font, GlyphAtlas, MAX_ATLAS_SIZE};
+    use crate::{pos2, vec2, Color, Painter, SoftwareRenderer};
+    use ab_glyph::Font as _;
+
+    /// Fira Sans, under the SIL Open Font License in `assets/fonts/OFL.txt`.
+    fn test_font() -> Font {
+        Font::from_static(include_bytes!("../assets/fonts/FiraSans-Regular.ttf")).unwrap()
+    }
+
+    #[test]
+    fn atlas_shelves_and_growth() {
+        let mut atlas = GlyphAtlas::default();
+        assert_eq!(atlas.allocate(300, 10), Some((0, 0)));
+        // doesn't fit next to the first one, starts a new shelf
+        assert_eq!(atlas.allocate(300, 20), Some((0, 10)));
+        assert_eq!(atlas.allocate(100, 5), Some((300, 10)));
+        assert_eq!(atlas.allocate(10, 600), None);
+
+        atlas.begin_frame();
+        assert_eq!(atlas.size, 1024);
+        assert_eq!(atlas.allocate(10, 600), Some((0, 0)));
+
+        // glyphs that can never fit are remembered, without filling the atlas
+        let font = test_font();
+        let id = font.font.glyph_id('H');
+        let size = MAX_ATLAS_SIZE as f32 * 4.0;
+        assert!(atlas.glyph(&font, id, size, 0).is_none());
+        assert!(!atlas.overflowed);
+        assert_eq!(atlas.glyphs.len(), 1);
+    }
+
+    #[test]
+    fn text_layout_and_rendering() {
+        let font = test_font();
+        let mut painter = Painter::default();
+        let mut fb = SoftwareRenderer::new(64, 64);
+        fb.attach(&mut painter);
+        painter.begin(64, 64);
+        painter.set_font(Some(font.clone()));
+
+        let (pos, size) = (pos2(4.0, 4.0), 16.0);
+        let bounds = painter.measure_text(pos, "Hi\nHi", size);
+        let advance = font.measure("H", size).x;
+        assert!(
+            (bounds.w - font.measure("Hi", size).x).abs() < 1e-3,
+            "{bounds:?}"
+        );
+        assert!(bounds.w > advance, "{bounds:?}");
+        let height = font.line_height(size) + font.ascent(size) - font.descent(size);
+        assert!((bounds.h - height).abs() < 1e-3, "{bounds:?}");
+
+        // nothing is laid out under a transform that collapses the plane
+        painter.push_transform();
+        painter.scale(vec2(0.0, 0.0));
+        painter.text(pos, "Hi", size, Color::WHITE);
+        painter.pop_transform();
+        assert!(painter.vertices.is_empty());
+
+        painter.text(pos, "Hi\nHi", size, Color::WHITE);
+        // drawing the same glyphs again doesn't rasterize anything
+        let version = painter.glyph_atlas.version;
+        painter.text(pos, "Hi", size, Color::WHITE);
+        assert_eq!(painter.glyph_atlas.version, version);
+        fb.render(&painter);
+
+        for y in 0..64 {
+            for x in 0..64 {
+                let p = pos2(x as f32 + 0.5, y as f32 + 0.5);
+                let covered = fb.pixel(x, y).a > 0;
+                assert!(
+                    !covered || bounds.contains(p),
+                    "({x}, {y}) is outside {bounds:?}"
+                );
+            }
+        }
+        // the H of each line stands on its baseline
+        for line in 0..2 {
+            let baseline =
+                (pos.y + font.ascent(size) + line as f32 * font.line_height(size)).round() as u32;
+            let row = |y| {
+                (4..4 + advance as u32)
+                    .map(|x| fb.pixel(x, y).a)
+                    .collect::<Vec<_>>()
+            };
+            // the stems aren't pixel aligned, but mostly cover a column
+            assert!(row(baseline - 1).iter().any(|&a| a >= 192), "line {line}");
+            assert!(row(baseline).iter().all(|&a| a == 0), "line {line}");
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{vec2, Vec2, XdError, XdResult};
+use glow::{Context, HasContext, PixelUnpackData};
+use std::rc::Rc;
+
+/// Identifies a texture in [`Command::Draw`](crate::Command::Draw).
+pub type TextureId = glow::Texture;
+
+/// How texels are sampled when a texture is minified or magnified.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum TextureFilter {
+    /// Pick the closest texel. Good for pixel art.
+    Nearest,
+    /// Blend the four closest texels.
+    #[default]
+    Linear,
+}
+
+impl TextureFilter {
+    /// The matching GL filter.
+    #[inline]
+    pub const fn to_gl(self) -> u32 {
+        match self {
+            Self::Nearest => glow::NEAREST,
+            Self::Linear => glow::LINEAR,
+        }
+    }
+}
+
+/// What happens to texture coordinates outside of `[0, 1]`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum TextureWrap {
+    /// Use the texel on the edge.
+    #[default]
+    ClampToEdge,
+    /// Tile the texture.
+    Repeat,
+    /// Tile the texture, mirroring every other tile.
+    MirroredRepeat,
+}
+
+impl TextureWrap {
+    /// The matching GL wrap mode.
+    #[inline]
+    pub const fn to_gl(self) -> u32 {
+        match self {
+            Self::ClampToEdge => glow::CLAMP_TO_EDGE,
+            Self::Repeat => glow::REPEAT,
+            Self::MirroredRepeat => glow::MIRRORED_REPEAT,
+        }
+    }
+}
+
+/// Sampling settings of a texture.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct TextureOptions {
+    /// Filter used when the texture is drawn smaller than its size.
+    pub min_filter: TextureFilter,
+    /// Filter used when the texture is drawn larger than its size.
+    pub mag_filter: TextureFilter,
+    /// Horizontal wrap mode.
+    pub wrap_x: TextureWrap,
+    /// Vertical wrap mode.
+    pub wrap_y: TextureWrap,
+}
+
+impl TextureOptions {
+    /// Nearest filtering, clamped to the edge.
+    pub const NEAREST: Self = Self {
+        min_filter: TextureFilter::Nearest,
+        mag_filter: TextureFilter::Nearest,
+        wrap_x: TextureWrap::ClampToEdge,
+        wrap_y: TextureWrap::ClampToEdge,
+    };
+
+    /// Linear filtering, clamped to the edge.
+    pub const LINEAR: Self = Self {
+        min_filter: TextureFilter::Linear,
+        mag_filter: TextureFilter::Linear,
+        wrap_x: TextureWrap::ClampToEdge,
+        wrap_y: TextureWrap::ClampToEdge,
+    };
+
+    /// Use the same filter for minification and magnification.
+    #[inline]
+    pub const fn with_filter(self, filter: TextureFilter) -> Self {
+        Self {
+            min_filter: filter,
+            mag_filter: filter,
+            ..self
+        }
+    }
+
+    /// Use the same wrap mode on both axes.
+    #[inline]
+    pub const fn with_wrap(self, wrap: TextureWrap) -> Self {
+        Self {
+            wrap_x: wrap,
+            wrap_y: wrap,
+            ..self
+        }
+    }
+}
+
+/// Check that `pixels` holds exactly `width * height` RGBA8 texels.
+pub(crate) fn check_rgba8_len(width: u32, height: u32, pixels: &[u8]) -> XdResult<()> {
+    let expected = width as usize * height as usize * 4;
+    if pixels.len() != expected {
+        return Err(XdError::InvalidPixelData {
+            expected,
+            got: pixels.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Whether the `width` x `height` region at `(x, y)` fits in a `size` texture.
+pub(crate) fn region_in_bounds(x: u32, y: u32, width: u32, height: u32, size: (u32, u32)) -> bool {
+    let fits = |start: u32, len, max| start.checked_add(len).is_some_and(|end| end <= max);
+    fits(x, width, size.0) && fits(y, height, size.1)
+}
+
+/// An RGBA8 texture living on the GPU.
+///
+/// The GL texture is freed when this is dropped.
+pub struct Texture {
+    gl: Rc<Context>,
+    texture: glow::Texture,
+    width: u32,
+    height: u32,
+    options: TextureOptions,
+}
+
+impl Texture {
+    /// Create a texture from tightly packed RGBA8 pixels, row by row from the top-left corner.
+    pub fn from_rgba8(
+        gl: &Rc<Context>,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+        options: TextureOptions,
+    ) -> XdResult<Self> {
+        check_rgba8_len(width, height, pixels)?;
+        let format = (glow::RGBA8, glow::RGBA, glow::UNSIGNED_BYTE);
+        Self::create(gl, width, height, format, Some(pixels), options)
+    }
+
+    /// Create a texture with the given GL `(internal_format, format, type)`,
+    /// uninitialized if `pixels` is `None`.
+    pub(crate) fn create(
+        gl: &Rc<Context>,
+        width: u32,
+        height: u32,
+        (internal_format, format, ty): (u32, u32, u32),
+        pixels: Option<&[u8]>,
+        options: TextureOptions,
+    ) -> XdResult<Self> {
+        let texture = unsafe {
+            let texture = gl.create_texture().map_err(XdError::Gl)?;
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                internal_format as i32,
+                width as i32,
+                height as i32,
+                0,
+                format,
+                ty,
+                pixels,
+            );
+            texture
+        };
+
+        let mut texture = Self {
+            gl: gl.clone(),
+            texture,
+            width,
+            height,
+            options,
+        };
+        texture.set_options(options);
+        Ok(texture)
+    }
+
+    /// Overwrite a `width` x `height` region of the texture, starting at `(x, y)`.
+    pub fn update(&self, x: u32, y: u32, width: u32, height: u32, pixels: &[u8]) -> XdResult<()> {
+        check_rgba8_len(width, height, pixels)?;
+        if !region_in_bounds(x, y, width, height, (self.width, self.height)) {
+            return Err(XdError::RegionOutOfBounds {
+                x,
+                y,
+                width,
+                height,
+            });
+        }
+
+        unsafe {
+            self.gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            self.gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            self.gl.tex_sub_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                x as i32,
+                y as i32,
+                width as i32,
+                height as i32,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                PixelUnpackData::Slice(pixels),
+            );
+        }
+        Ok(())
+    }
+
+    /// Change the filtering and wrapping of the texture.
+    pub fn set_options(&mut self, options: TextureOptions) {
+        self.options = options;
+        unsafe {
+            let gl = &self.gl;
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MIN_FILTER,
+                options.min_filter.to_gl() as i32,
+            );
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MAG_FILTER,
+                options.mag_filter.to_gl() as i32,
+            );
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_WRAP_S,
+                options.wrap_x.to_gl() as i32,
+            );
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_WRAP_T,
+                options.wrap_y.to_gl() as i32,
+            );
+        }
+    }
+
+    /// Handle used to refer to this texture in draw commands.
+    #[inline]
+    pub fn id(&self) -> TextureId {
+        self.texture
+    }
+
+    #[inline]
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Size in texels.
+    #[inline]
+    pub fn size(&self) -> Vec2 {
+        vec2(self.width as f32, self.height as f32)
+    }
+
+    #[inline]
+    pub const fn options(&self) -> TextureOptions {
+        self.options
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe { self.gl.delete_texture(self.texture) };
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{Color, Mat2x3, Pos2, Vec2};
+use glow::{Context, HasContext, Program, UniformLocation};
+use std::collections::HashMap;
+
+/// Texture unit a `sampler2D` uniform reads from, `TextureSlot(0)` being the
+/// unit the painter binds draw textures to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct TextureSlot(pub u32);
+
+/// Value of a shader uniform.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum UniformValue {
+    /// `float`.
+    Float(f32),
+    /// `vec2`.
+    Vec2(Vec2),
+    /// `vec2`, from a position.
+    Pos2(Pos2),
+    /// `vec4` with components in `[0, 1]`, not premultiplied.
+    Color(Color),
+    /// `mat3`, with the implicit `[0, 0, 1]` row.
+    Mat2x3(Mat2x3),
+    /// `int` or `bool`.
+    Int(i32),
+    /// `sampler2D`.
+    Texture(TextureSlot),
+}
+
+impl UniformValue {
+    /// Set the uniform at `location` of the bound program.
+    ///
+    /// # Safety
+    ///
+    /// `gl` must be the current GL context, with the program `location` belongs to bound.
+    pub unsafe fn set(&self, gl: &Context, location: &UniformLocation) {
+        let location = Some(location);
+        match *self {
+            Self::Float(v) => gl.uniform_1_f32(location, v),
+            Self::Vec2(v) => gl.uniform_2_f32(location, v.x, v.y),
+            Self::Pos2(p) => gl.uniform_2_f32(location, p.x, p.y),
+            Self::Color(c) => gl.uniform_4_f32(
+                location,
+                c.r as f32 / 255.0,
+                c.g as f32 / 255.0,
+                c.b as f32 / 255.0,
+                c.a as f32 / 255.0,
+            ),
+            Self::Mat2x3(m) => {
+                gl.uniform_matrix_3_f32_slice(location, false, &m.to_cols_array_3x3())
+            }
+            Self::Int(v) => gl.uniform_1_i32(location, v),
+            Self::Texture(TextureSlot(unit)) => gl.uniform_1_i32(location, unit as i32),
+        }
+    }
+}
+
+macro_rules! impl_from_uniform {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$ty> for UniformValue {
+                #[inline]
+                fn from(v: $ty) -> Self {
+                    Self::$variant(v)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_uniform!(
+    f32 => Float,
+    Vec2 => Vec2,
+    Pos2 => Pos2,
+    Color => Color,
+    Mat2x3 => Mat2x3,
+    i32 => Int,
+    TextureSlot => Texture,
+);
+
+impl From<bool> for UniformValue {
+    #[inline]
+    fn from(v: bool) -> Self {
+        Self::Int(v as i32)
+    }
+}
+
+/// Named uniform values, set in order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Uniforms {
+    values: Vec<(String, UniformValue)>,
+}
+
+impl Uniforms {
+    #[inline]
+    pub const fn new() -> Self {
+        Self { values: Vec::new() }
+    }
+
+    /// Set `name` to `value`, replacing its previous value.
+    pub fn set(&mut self, name: &str, value: impl Into<UniformValue>) {
+        let value = value.into();
+        match self.values.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value,
+            None => self.values.push((name.to_owned(), value)),
+        }
+    }
+
+    /// Builder version of [`Uniforms::set`].
+    #[inline]
+    pub fn with(mut self, name: &str, value: impl Into<UniformValue>) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<UniformValue> {
+        self.iter().find(|&(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&str, UniformValue)> {
+        self.values.iter().map(|(n, v)| (n.as_str(), *v))
+    }
+}
+
+/// Uniform locations of a program, looked up once per name.
+#[derive(Debug, Default)]
+pub(crate) struct UniformCache {
+    locations: HashMap<String, Option<UniformLocation>>,
+}
+
+impl UniformCache {
+    /// Location of `name` in `program`, warning once if the program doesn't have it.
+    ///
+    /// # Safety
+    ///
+    /// `gl` must be the current GL context, `program` one of its linked programs.
+    pub unsafe fn location(
+        &mut self,
+        gl: &Context,
+        program: Program,
+        name: &str,
+    ) -> Option<&UniformLocation> {
+        if !self.locations.contains_key(name) {
+            let location = gl.get_uniform_location(program, name);
+            if location.is_none() {
+                log::warn!("unknown uniform {name:?}, it may have been optimized out");
+            }
+            self.locations.insert(name.to_owned(), location);
+        }
+        self.locations[name].as_ref()
+    }
+
+    /// Set every uniform in `uniforms` that `program` has.
+    ///
+    /// # Safety
+    ///
+    /// `gl` must be the current GL context, with `program` bound.
+    pub unsafe fn set_all(&mut self, gl: &Context, program: Program, uniforms: &Uniforms) {
+        for (name, value) in uniforms.iter() {
+            if let Some(location) = self.location(gl, program, name) {
+                value.set(gl, location);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TextureSlot, UniformValue, Uniforms};
+    use crate::{rect, vec2, Color, Command, Painter};
+
+    #[test]
+    fn per_draw_uniforms() {
+        let mut uniforms = Uniforms::new()
+            .with("u_time", 1.0)
+            .with("u_mask", TextureSlot(1));
+        uniforms.set("u_time", 2.0);
+        assert_eq!(uniforms.get("u_time"), Some(UniformValue::Float(2.0)));
+        assert_eq!(uniforms.iter().count(), 2);
+
+        let mut painter = Painter::default();
+        painter.begin(8, 8);
+        painter.filled_rect(rect(0.0, 0.0, 1.0, 1.0), Color::RED);
+        painter.set_uniform("u_offset", vec2(1.0, 0.0));
+        painter.filled_rect(rect(2.0, 0.0, 1.0, 1.0), Color::RED);
+        painter.filled_rect(rect(4.0, 0.0, 1.0, 1.0), Color::RED);
+        painter.set_uniform("u_offset", vec2(2.0, 0.0));
+        painter.filled_rect(rect(6.0, 0.0, 1.0, 1.0), Color::RED);
+        painter.clear_uniforms();
+        painter.filled_rect(rect(0.0, 2.0, 1.0, 1.0), Color::RED);
+
+        // draws with the same uniforms merge and share an entry
+        let draws: Vec<_> = painter
+            .commands
+            .iter()
+            .filter_map(|c| match *c {
+                Command::DrawIndexed { uniforms, .. } => Some(uniforms),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(draws, [None, Some(0), Some(1), None]);
+        assert_eq!(painter.uniforms.len(), 2);
+        assert_eq!(
+            painter.uniforms[1].get("u_offset"),
+            Some(UniformValue::Vec2(vec2(2.0, 0.0)))
+        );
+    }
+}
@@ -1,9 +1,17 @@
-use crate::{App, Backend, WindowSettings, XdResult};
+use crate::{App, Backend, Image, Painter, WindowSettings, XdResult};
+use std::rc::Rc;
 pub struct Xd {
     pub(crate) width: u32,  // modified in backend
     pub(crate) height: u32, // modified in backend
     pub window_settings: WindowSettings,
     pub ignore_swapbuffers: bool,
+    /// Records the draw commands for the current frame.
+    pub painter: Painter,
+    pub(crate) gl: Option<Rc<glow::Context>>, // set in backend
+    /// Whether to capture the frame being drawn, checked in backend.
+    pub(crate) screenshot_requested: bool,
+    /// Frame captured after [`Xd::request_screenshot`].
+    pub(crate) screenshot: Option<Image>,
 }
 
 impl Default for Xd {
@@ -19,6 +27,10 @@ impl Xd {
             height: 0,
             window_settings: WindowSettings::default(),
             ignore_swapbuffers: false,
+            painter: Painter::default(),
+            gl: None,
+            screenshot_requested: false,
+            screenshot: None,
         }
     }
 
@@ -37,4 +49,31 @@ impl Xd {
     pub const fn height(&self) -> u32 {
         self.height
     }
+
+    /// The GL context, used to create GPU resources such as textures.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called before the window is opened, i.e. before [`App::init`].
+    #[inline]
+    pub fn gl(&self) -> &Rc<glow::Context> {
+        self.gl
+            .as_ref()
+            .expect("the GL context is only available once the window is open")
+    }
+
+    /// Capture the frame being drawn once it's rendered, before the buffers are swapped.
+    ///
+    /// The image is available from [`Xd::screenshot`] while drawing the next frame.
+    #[inline]
+    pub fn request_screenshot(&mut self) {
+        self.screenshot_requested = true;
+    }
+
+    /// Take the frame captured after [`Xd::request_screenshot`], if it's been
+    /// rendered. The image has a top-left origin, see [`Image::save_png`].
+    #[inline]
+    pub fn screenshot(&mut self) -> Option<Image> {
+        self.screenshot.take()
+    }
 }